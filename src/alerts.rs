@@ -0,0 +1,284 @@
+use crate::types::{AlertConfig, AlertEvent, Opportunity, OpportunityView, Symbol};
+use crate::utils::constants::{ALERT_CAPACITY, ALERT_TIMEOUT_SECS};
+use std::collections::{HashMap, VecDeque};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+static ALERTER: OnceLock<Alerter> = OnceLock::new();
+
+struct Alerter {
+    sender: mpsc::Sender<AlertEvent>,
+    min_profit: HashMap<Symbol, f64>,
+}
+
+/// Starts the webhook dispatcher. Alerts raised before, or without any webhook configured, are
+/// dropped.
+pub fn start_alerts(config: &AlertConfig) {
+    if config.webhooks.is_empty() {
+        return;
+    }
+
+    let (sender, receiver) = mpsc::channel(ALERT_CAPACITY);
+    tokio::spawn(Dispatcher::new(config.clone()).run(receiver));
+
+    let alerter = Alerter {
+        sender,
+        min_profit: config.min_profit.clone(),
+    };
+    if ALERTER.set(alerter).is_err() {
+        tracing::warn!("Alerts already started");
+    }
+}
+
+pub fn alert(event: AlertEvent) {
+    let Some(alerter) = ALERTER.get() else {
+        return;
+    };
+
+    if alerter.sender.try_send(event).is_err() {
+        tracing::warn!("Alert queue full, dropping alert");
+    }
+}
+
+/// Alerts when the worst-case profit of any asset reaches its configured minimum.
+pub fn alert_opportunity(opportunity: &Opportunity, profit: &HashMap<Symbol, f64>) {
+    let Some(alerter) = ALERTER.get() else {
+        return;
+    };
+
+    let above_threshold = profit.iter().any(|(symbol, amount)| {
+        alerter.min_profit.get(symbol).is_some_and(|min| amount >= min)
+    });
+    if above_threshold {
+        alert(AlertEvent::Opportunity {
+            opportunity: OpportunityView::from(opportunity),
+            profit: profit.clone(),
+        });
+    }
+}
+
+/// Stream errors seen within the configured window.
+pub struct ErrorWindow {
+    window: Duration,
+    threshold: usize,
+    errors: VecDeque<Instant>,
+}
+
+impl ErrorWindow {
+    pub fn new(config: &AlertConfig) -> Self {
+        Self {
+            window: Duration::from_secs(config.error_window_secs),
+            threshold: config.error_threshold,
+            errors: VecDeque::new(),
+        }
+    }
+
+    /// Records an error and returns the number of errors in the window once it reaches the
+    /// threshold.
+    pub fn record(&mut self) -> Option<usize> {
+        let now = Instant::now();
+        self.errors.push_back(now);
+        while self.errors.front().is_some_and(|error| now - *error > self.window) {
+            self.errors.pop_front();
+        }
+
+        (self.errors.len() >= self.threshold).then_some(self.errors.len())
+    }
+
+    pub fn window_secs(&self) -> u64 {
+        self.window.as_secs()
+    }
+}
+
+struct Dispatcher {
+    client: reqwest::Client,
+    config: AlertConfig,
+    /// When each alert key was last sent, for deduplication
+    last_sent: HashMap<String, Instant>,
+    /// Send times within the last minute, for rate limiting
+    sent: VecDeque<Instant>,
+    /// Alerts dropped by the rate limit since the last one sent
+    suppressed: usize,
+}
+
+impl Dispatcher {
+    fn new(config: AlertConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(ALERT_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            client,
+            config,
+            last_sent: HashMap::new(),
+            sent: VecDeque::new(),
+            suppressed: 0,
+        }
+    }
+
+    async fn run(mut self, mut receiver: mpsc::Receiver<AlertEvent>) {
+        let dedup = Duration::from_secs(self.config.dedup_secs);
+        let minute = Duration::from_secs(60);
+
+        while let Some(event) = receiver.recv().await {
+            let now = Instant::now();
+            let key = event.key();
+
+            self.last_sent.retain(|_, sent| now - *sent < dedup);
+            if self.last_sent.contains_key(&key) {
+                tracing::debug!("Duplicate alert {} dropped", key);
+                continue;
+            }
+
+            while self.sent.front().is_some_and(|sent| now - *sent >= minute) {
+                self.sent.pop_front();
+            }
+            if self.sent.len() >= self.config.max_per_minute {
+                self.suppressed += 1;
+                tracing::warn!("Alert rate limit reached, dropping {}", key);
+                continue;
+            }
+
+            self.last_sent.insert(key, now);
+            self.sent.push_back(now);
+
+            let payload = self.payload(&event);
+            self.suppressed = 0;
+            for (i, url) in self.config.webhooks.iter().enumerate() {
+                tokio::spawn(post(self.client.clone(), i, url.clone(), payload.clone()));
+            }
+        }
+    }
+
+    /// The event's fields plus a chat-ready `text`, the send time and how many alerts the rate
+    /// limit dropped before this one.
+    fn payload(&self, event: &AlertEvent) -> serde_json::Value {
+        let mut payload = serde_json::to_value(event).unwrap_or_default();
+        if let Some(fields) = payload.as_object_mut() {
+            fields.insert("text".into(), event.summary().into());
+            fields.insert("timestamp_ms".into(), chrono::Utc::now().timestamp_millis().into());
+            fields.insert("suppressed".into(), self.suppressed.into());
+        }
+
+        payload
+    }
+}
+
+/// Webhook URLs usually embed a secret, errors only name the webhook by its index.
+async fn post(client: reqwest::Client, index: usize, url: String, payload: serde_json::Value) {
+    let result = client
+        .post(&url)
+        .json(&payload)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+
+    if let Err(e) = result {
+        tracing::error!("Failed to deliver alert to webhook {}: {}", index, e.without_url());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Json;
+    use axum::Router;
+    use axum::extract::State;
+    use axum::routing::post;
+    use tokio::net::TcpListener;
+
+    /// Local webhook that forwards every payload it receives.
+    async fn webhook() -> (String, mpsc::UnboundedReceiver<serde_json::Value>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let app = Router::new()
+            .route(
+                "/hook",
+                post(
+                    |State(sender): State<mpsc::UnboundedSender<serde_json::Value>>,
+                     Json(payload): Json<serde_json::Value>| async move {
+                        let _ = sender.send(payload);
+                    },
+                ),
+            )
+            .with_state(sender);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (url, receiver)
+    }
+
+    fn risk_limit(subject: &str) -> AlertEvent {
+        AlertEvent::RiskLimit {
+            limit: "max_exposure".to_string(),
+            subject: subject.to_string(),
+            message: format!("{subject} over the limit"),
+        }
+    }
+
+    /// Runs a dispatcher over `events` and returns the payloads the webhook received, ordered
+    /// by subject since deliveries race each other.
+    async fn dispatch(config: AlertConfig, events: Vec<AlertEvent>) -> Vec<serde_json::Value> {
+        let (url, mut received) = webhook().await;
+        let config = AlertConfig {
+            webhooks: vec![url],
+            ..config
+        };
+
+        let (sender, receiver) = mpsc::channel(ALERT_CAPACITY);
+        for event in events {
+            sender.send(event).await.unwrap();
+        }
+        drop(sender);
+        Dispatcher::new(config).run(receiver).await;
+
+        let mut payloads = vec![];
+        while let Ok(Some(payload)) =
+            tokio::time::timeout(Duration::from_millis(500), received.recv()).await
+        {
+            payloads.push(payload);
+        }
+        payloads.sort_by_key(|payload| payload["subject"].as_str().unwrap_or("").to_string());
+
+        payloads
+    }
+
+    #[tokio::test]
+    async fn posts_event_fields_with_summary() {
+        let payloads = dispatch(AlertConfig::default(), vec![risk_limit("base")]).await;
+
+        assert_eq!(payloads.len(), 1);
+        let payload = &payloads[0];
+        assert_eq!(payload["type"], "risk_limit");
+        assert_eq!(payload["limit"], "max_exposure");
+        assert_eq!(payload["subject"], "base");
+        assert_eq!(payload["text"], "Risk limit max_exposure breached: base over the limit");
+        assert_eq!(payload["suppressed"], 0);
+        assert!(payload["timestamp_ms"].as_i64().is_some_and(|ms| ms > 0));
+    }
+
+    #[tokio::test]
+    async fn drops_duplicates_within_dedup_window() {
+        let events = vec![risk_limit("base"), risk_limit("base"), risk_limit("ethereum")];
+        let payloads = dispatch(AlertConfig::default(), events).await;
+
+        let subjects = payloads.iter().map(|p| p["subject"].clone()).collect::<Vec<_>>();
+        assert_eq!(subjects, ["base", "ethereum"]);
+    }
+
+    #[tokio::test]
+    async fn drops_alerts_over_rate_limit() {
+        let config = AlertConfig {
+            max_per_minute: 2,
+            ..AlertConfig::default()
+        };
+        let events = vec![risk_limit("a"), risk_limit("b"), risk_limit("c"), risk_limit("d")];
+        let payloads = dispatch(config, events).await;
+
+        let subjects = payloads.iter().map(|p| p["subject"].clone()).collect::<Vec<_>>();
+        assert_eq!(subjects, ["a", "b"]);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::graph_export::{GraphFilter, GraphFormat, dump_graph};
+use crate::server::ServerState;
+use crate::types::{EdgeSnapshot, PriceEdgeView, Protocol, SpreadView, TokenNode};
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::{StatusCode, header};
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tycho_common::models::Chain;
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, String)>;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EdgeQuery {
+    pub chain: Option<String>,
+    pub protocol: Option<String>,
+    /// Matches edges with this asset on either side
+    pub symbol: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct GraphQuery {
+    #[serde(default)]
+    pub format: GraphFormat,
+    pub chain: Option<String>,
+    /// Keeps only edges touching this asset
+    pub symbol: Option<String>,
+}
+
+fn bad_request(message: String) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, message)
+}
+
+fn parse_chain(name: Option<&str>) -> Result<Option<Chain>, (StatusCode, String)> {
+    name.map(|name| {
+        Chain::from_str(name).map_err(|_| bad_request(format!("Unknown chain: {name}")))
+    })
+    .transpose()
+}
+
+pub async fn get_tokens(State(state): State<ServerState>) -> Json<Vec<TokenNode>> {
+    let graph = state.graph.borrow().clone();
+
+    Json(graph.graph.node_weights().cloned().collect())
+}
+
+pub async fn get_edges(
+    State(state): State<ServerState>,
+    Query(query): Query<EdgeQuery>,
+) -> ApiResult<Vec<PriceEdgeView>> {
+    let chain = parse_chain(query.chain.as_deref())?;
+    let protocol = match query.protocol.as_deref() {
+        Some(name) => Some(
+            Protocol::from_str(name)
+                .ok_or_else(|| bad_request(format!("Unknown protocol: {name}")))?,
+        ),
+        None => None,
+    };
+
+    let graph = state.graph.borrow().clone();
+
+    let edges = graph
+        .graph
+        .edge_weights()
+        .filter(|edge| chain.is_none_or(|chain| edge.view.chain == chain))
+        .filter(|edge| protocol.is_none_or(|protocol| edge.view.protocol == protocol.to_str()))
+        .filter(|edge| {
+            query.symbol.as_deref().is_none_or(|symbol| {
+                edge.from_asset.eq_ignore_ascii_case(symbol)
+                    || edge.to_asset.eq_ignore_ascii_case(symbol)
+            })
+        })
+        .map(|edge| edge.view.clone())
+        .collect();
+
+    Ok(Json(edges))
+}
+
+/// Both directions of a pool.
+pub async fn get_pool(
+    State(state): State<ServerState>,
+    Path(address): Path<String>,
+) -> ApiResult<Vec<PriceEdgeView>> {
+    let graph = state.graph.borrow().clone();
+
+    let edges = graph
+        .edges_map
+        .get(&address.to_lowercase())
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Unknown pool: {address}")))?
+        .iter()
+        .filter_map(|idx| graph.graph.edge_weight(*idx))
+        .map(|edge| edge.view.clone())
+        .collect();
+
+    Ok(Json(edges))
+}
+
+/// For every asset `symbol` trades against, the best and worst price over all chains and pools.
+pub async fn get_spreads(
+    State(state): State<ServerState>,
+    Path(symbol): Path<String>,
+) -> ApiResult<Vec<SpreadView>> {
+    let graph = state.graph.borrow().clone();
+
+    if !graph.nodes_map.keys().any(|s| s.eq_ignore_ascii_case(&symbol)) {
+        return Err((StatusCode::NOT_FOUND, format!("Unknown symbol: {symbol}")));
+    }
+
+    let mut by_quote: HashMap<String, Vec<&PriceEdgeView>> = HashMap::new();
+    for EdgeSnapshot { view, from_asset, to_asset } in graph.graph.edge_weights() {
+        if from_asset.eq_ignore_ascii_case(&symbol) && view.price > 0.0 {
+            by_quote.entry(to_asset.clone()).or_default().push(view);
+        }
+    }
+
+    let mut spreads = by_quote
+        .into_iter()
+        .map(|(quote, edges)| {
+            let best = edges.iter().max_by(|a, b| a.price.total_cmp(&b.price)).unwrap();
+            let worst = edges.iter().min_by(|a, b| a.price.total_cmp(&b.price)).unwrap();
+
+            SpreadView {
+                quote,
+                spread_bps: (best.price / worst.price - 1.0) * 10_000.0,
+                best: (*best).clone(),
+                worst: (*worst).clone(),
+            }
+        })
+        .collect::<Vec<_>>();
+    spreads.sort_by(|a, b| b.spread_bps.total_cmp(&a.spread_bps));
+
+    Ok(Json(spreads))
+}
+
+/// The graph, or the part selected by chain and symbol, as Graphviz DOT or GraphML.
+pub async fn get_graph(
+    State(state): State<ServerState>,
+    Query(query): Query<GraphQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let filter = GraphFilter {
+        chain: parse_chain(query.chain.as_deref())?,
+        symbol: query.symbol,
+    };
+
+    let graph = state.graph.borrow().clone();
+    let body = dump_graph(&graph, &filter, query.format);
+
+    Ok(([(header::CONTENT_TYPE, query.format.content_type())], body))
+}
@@ -0,0 +1,280 @@
+use crate::tx_manager::TxManager;
+use crate::types::{Network, SignedPermit, TransferMode, TxEvent};
+use crate::utils::abi::{IERC20, IPermit2, PermitDetails, PermitSingle};
+use crate::utils::constants::{
+    APPROVAL_CHECK_INTERVAL_SECS, PERMIT_REFRESH_MARGIN_SECS, PERMIT_VALIDITY_SECS,
+};
+use crate::utils::rpc::{create_provider, create_signer};
+use alloy::network::TransactionBuilder;
+use alloy::primitives::aliases::{U48, U160};
+use alloy::primitives::{Address, U256};
+use alloy::providers::DynProvider;
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::Signer;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::sol_types::{SolCall, SolStruct, eip712_domain};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+use tycho_common::Bytes;
+use tycho_common::models::Chain;
+
+type TokenKey = (Chain, Bytes);
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// The `PermitSingle` a cached permit was signed over, permits always grant the max amount.
+pub fn permit_single(permit: &SignedPermit) -> PermitSingle {
+    PermitSingle {
+        details: PermitDetails {
+            token: Address::from_slice(&permit.token),
+            amount: U160::MAX,
+            expiration: U48::from(permit.expiration),
+            nonce: U48::from(permit.nonce),
+        },
+        spender: Address::from_slice(&permit.spender),
+        sigDeadline: U256::from(permit.sig_deadline),
+    }
+}
+
+struct ChainApprovals {
+    network: Network,
+    provider: DynProvider,
+    tx_manager: Arc<TxManager>,
+    router: Address,
+    permit2: Address,
+}
+
+/// Keeps the allowances of each chain's transfer mode in place ahead of time: a router approval,
+/// or a Permit2 approval plus a signed permit. The execution path only reads the cached state
+/// and never waits on an approval transaction.
+pub struct ApprovalManager {
+    chains: HashMap<Chain, ChainApprovals>,
+    signer: PrivateKeySigner,
+    approved: RwLock<HashSet<TokenKey>>,
+    permits: RwLock<HashMap<TokenKey, SignedPermit>>,
+}
+
+impl ApprovalManager {
+    pub fn new(
+        networks: &[Network],
+        private_key: &str,
+        tx_managers: &HashMap<Chain, Arc<TxManager>>,
+    ) -> Result<Self, anyhow::Error> {
+        let mut chains = HashMap::new();
+
+        for network in networks {
+            let tx_manager = tx_managers
+                .get(&network.chain)
+                .ok_or_else(|| anyhow::anyhow!("No tx manager for {}", network.name))?;
+
+            chains.insert(
+                network.chain,
+                ChainApprovals {
+                    network: network.clone(),
+                    provider: create_provider(network)?,
+                    tx_manager: Arc::clone(tx_manager),
+                    router: Address::from_str(&network.router)?,
+                    permit2: Address::from_str(&network.permit2)?,
+                },
+            );
+        }
+
+        Ok(Self {
+            chains,
+            signer: create_signer(private_key)?,
+            approved: RwLock::new(HashSet::new()),
+            permits: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Whether the router may pull `token` on `chain` with a plain `transferFrom`.
+    pub fn is_approved(&self, chain: Chain, token: &Bytes) -> bool {
+        self.approved.read().unwrap().contains(&(chain, token.clone()))
+    }
+
+    /// A cached permit for the router that is not about to expire.
+    pub fn permit(&self, chain: Chain, token: &Bytes) -> Option<SignedPermit> {
+        let permits = self.permits.read().unwrap();
+        let permit = permits.get(&(chain, token.clone()))?;
+
+        (permit.sig_deadline > now_secs() + PERMIT_REFRESH_MARGIN_SECS).then(|| permit.clone())
+    }
+
+    /// Permit2 consumes a permit's nonce when an included transaction uses it, so the cached
+    /// permit is re-signed over the next nonce. The permit is dropped if that fails, and signed
+    /// again by `run_approvals`.
+    pub async fn permit_used(&self, chain: Chain, used: &SignedPermit) {
+        let key = (chain, used.token.clone());
+        let cached = self.permits.read().unwrap().get(&key).map(|permit| permit.nonce);
+        if cached != Some(used.nonce) {
+            return;
+        }
+
+        let Some(chain_approvals) = self.chains.get(&chain) else {
+            return;
+        };
+        let token = Address::from_slice(&used.token);
+        let next = U48::from(used.nonce + 1);
+
+        match self.sign_permit(chain_approvals, token, next).await {
+            Ok(permit) => {
+                self.permits.write().unwrap().insert(key, permit);
+            }
+            Err(e) => {
+                tracing::error!("Failed to re-sign the permit for {} on {:?}: {}", token, chain, e);
+                self.permits.write().unwrap().remove(&key);
+            }
+        }
+    }
+
+    /// Approves `spender` for the max amount unless the allowance is already large enough.
+    async fn ensure_allowance(
+        &self,
+        chain: &ChainApprovals,
+        token: Address,
+        spender: Address,
+    ) -> Result<(), anyhow::Error> {
+        let erc20 = IERC20::new(token, &chain.provider);
+        let allowance = erc20.allowance(self.signer.address(), spender).call().await?;
+
+        if allowance >= U256::MAX >> 1 {
+            return Ok(());
+        }
+
+        tracing::info!("Approving {} for {} on {}", spender, token, chain.network.name);
+
+        let data = IERC20::approveCall {
+            spender,
+            amount: U256::MAX,
+        }
+        .abi_encode();
+        let tx = TransactionRequest::default().with_to(token).with_input(data);
+        let id = format!("approve-{}-{}", token, spender);
+
+        match chain.tx_manager.execute_request(&id, tx).await? {
+            TxEvent::Included { .. } => Ok(()),
+            event => Err(anyhow::anyhow!("Approval did not go through: {:?}", event)),
+        }
+    }
+
+    /// Nonce the next permit of `token` for the router must be signed over.
+    async fn permit_nonce(
+        &self,
+        chain: &ChainApprovals,
+        token: Address,
+    ) -> Result<U48, anyhow::Error> {
+        let permit2 = IPermit2::new(chain.permit2, &chain.provider);
+        let allowance = permit2
+            .allowance(self.signer.address(), token, chain.router)
+            .call()
+            .await?;
+
+        Ok(allowance.nonce)
+    }
+
+    async fn sign_permit(
+        &self,
+        chain: &ChainApprovals,
+        token: Address,
+        nonce: U48,
+    ) -> Result<SignedPermit, anyhow::Error> {
+        let expiration = now_secs() + PERMIT_VALIDITY_SECS;
+        let permit = PermitSingle {
+            details: PermitDetails {
+                token,
+                amount: U160::MAX,
+                expiration: U48::from(expiration),
+                nonce,
+            },
+            spender: chain.router,
+            sigDeadline: U256::from(expiration),
+        };
+
+        let domain = eip712_domain! {
+            name: "Permit2",
+            chain_id: chain.network.chainid,
+            verifying_contract: chain.permit2,
+        };
+        let signature = self
+            .signer
+            .sign_hash(&permit.eip712_signing_hash(&domain))
+            .await?;
+
+        Ok(SignedPermit {
+            token: Bytes::from(token.to_vec()),
+            spender: Bytes::from(chain.router.to_vec()),
+            nonce: nonce.to::<u64>(),
+            expiration,
+            sig_deadline: expiration,
+            signature: signature.as_bytes().to_vec(),
+        })
+    }
+
+    /// Brings the approval or permit of one token up to date for the chain's transfer mode and
+    /// caches the result.
+    pub async fn prepare(&self, chain: Chain, token: &Bytes) -> Result<(), anyhow::Error> {
+        let chain_approvals = self
+            .chains
+            .get(&chain)
+            .ok_or_else(|| anyhow::anyhow!("No approvals configured for {:?}", chain))?;
+        let token_address = Address::from_slice(token);
+        let key = (chain, token.clone());
+
+        match chain_approvals.network.transfer_mode {
+            TransferMode::Approval if !self.is_approved(chain, token) => {
+                self.ensure_allowance(chain_approvals, token_address, chain_approvals.router)
+                    .await?;
+                self.approved.write().unwrap().insert(key);
+            }
+            TransferMode::Permit2 if self.permit(chain, token).is_none() => {
+                self.ensure_allowance(chain_approvals, token_address, chain_approvals.permit2)
+                    .await?;
+                let nonce = self.permit_nonce(chain_approvals, token_address).await?;
+                let permit = self.sign_permit(chain_approvals, token_address, nonce).await?;
+                self.permits.write().unwrap().insert(key, permit);
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Keeps approvals of every traded token fresh in the background, and prepares the tokens of a
+/// reloaded chain as soon as they change.
+pub async fn run_approvals(
+    approvals: Arc<ApprovalManager>,
+    mut tokens: watch::Receiver<HashMap<Chain, Vec<Bytes>>>,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(APPROVAL_CHECK_INTERVAL_SECS));
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            changed = tokens.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+            }
+        }
+
+        let current = tokens.borrow_and_update().clone();
+        for (chain, chain_tokens) in &current {
+            for token in chain_tokens {
+                if let Err(e) = approvals.prepare(*chain, token).await {
+                    tracing::error!(
+                        "Failed to prepare approvals for {} on {:?}: {}",
+                        token,
+                        chain,
+                        e
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,42 +1,227 @@
-use crate::types::{ArbitrageGraph, PriceEdge, Protocol, TokenNode};
+use crate::flash_loan::flash_sources;
+use crate::types::{
+    ArbitrageGraph, AssetGroup, BlockTiming, ChainStatus, EdgeSnapshot, GraphSnapshot, Network,
+    PegType, PriceEdge, PriceEdgeView, Protocol, Symbol, TokenNode,
+};
+use crate::utils::constants::PRICE_HISTORY_LEN;
 use crate::utils::graph::GraphIndexUpdateTrait;
-use petgraph::graph::DiGraph;
-use petgraph::Direction::{Incoming, Outgoing};
+use petgraph::stable_graph::StableDiGraph;
 use petgraph::prelude::{EdgeIndex, NodeIndex};
-use petgraph::visit::EdgeRef;
 use std::collections::HashMap;
+use std::time::Instant;
 use tycho_common::{models::Chain, Bytes};
 use tycho_simulation::protocol::models::{BlockUpdate, ProtocolComponent};
+use tracing::warn;
 use tycho_simulation::{models::Token, protocol::state::ProtocolSim};
 
 impl ArbitrageGraph {
-    pub fn new() -> Self {
+    pub fn new(asset_groups: Vec<AssetGroup>) -> Self {
         Self {
-            graph: DiGraph::new(),
+            graph: StableDiGraph::new(),
             edges_map: HashMap::new(),
             nodes_map: HashMap::new(),
+            token_nodes: HashMap::new(),
+            asset_groups,
+            price_history: HashMap::new(),
+            feed: None,
+            chain_status: HashMap::new(),
+            recorder: None,
+            flash_entries: HashMap::new(),
+            initialized: false,
         }
     }
 
+    /// Read-only copy served by the HTTP API and the dashboard. Edges are reduced to their
+    /// views, no pool state is copied. Executable prices are carried over from `previous` for
+    /// edges whose spot price did not move, the other edges with a reference size are returned
+    /// with a copy of their state, to be quoted once the graph is unlocked.
+    pub fn snapshot(
+        &self,
+        previous: &GraphSnapshot,
+        reference_sizes: &HashMap<Symbol, f64>,
+    ) -> (GraphSnapshot, Vec<(EdgeIndex, PriceEdge, f64)>) {
+        let mut unquoted = vec![];
+
+        let graph = self.graph.map(
+            |_, node| node.clone(),
+            |idx, edge| {
+                let (from_asset, _) = self.asset_for(edge.chain, &edge.from_token);
+                let (to_asset, _) = self.asset_for(edge.chain, &edge.to_token);
+                let view = PriceEdgeView::from(edge);
+
+                let executable_price = match previous.graph.edge_weight(idx) {
+                    Some(last)
+                        if last.view.pool_address == view.pool_address
+                            && last.view.from_token.address == view.from_token.address
+                            && last.view.price == view.price =>
+                    {
+                        last.executable_price
+                    }
+                    _ => {
+                        if let Some(size) = reference_sizes.get(&from_asset) {
+                            unquoted.push((idx, edge.clone(), *size));
+                        }
+                        None
+                    }
+                };
+
+                EdgeSnapshot {
+                    view,
+                    from_asset,
+                    to_asset,
+                    executable_price,
+                }
+            },
+        );
+
+        let snapshot = GraphSnapshot {
+            graph,
+            edges_map: self.edges_map.clone(),
+            nodes_map: self.nodes_map.clone(),
+            chain_status: self.chain_status.clone(),
+            initialized: self.initialized,
+        };
+
+        (snapshot, unquoted)
+    }
+
     // TODO: decide if we need to call component (edge) synchronization
     pub fn initialize(&mut self, chain_tokens: HashMap<Chain, HashMap<Bytes, Token>>) {
-        let mut symbol_to_tokens: HashMap<String, HashMap<Chain, Token>> = HashMap::new();
-
         for (chain, tokens) in chain_tokens {
             for (_, token) in tokens {
-                symbol_to_tokens
-                    .entry(token.symbol.clone())
-                    .or_insert_with(HashMap::new)
-                    .insert(chain, token);
+                self.add_token(chain, token);
+            }
+        }
+        self.initialized = true;
+    }
+
+    /// Resolves the asset a token belongs to. Tokens outside of any configured group are
+    /// merged by symbol, unless the symbol names a group: such a token stays a separate asset
+    /// of its chain rather than joining a group it is not a member of.
+    pub fn asset_for(&self, chain: Chain, token: &Token) -> (Symbol, PegType) {
+        let key = (chain, token.address.clone());
+
+        if let Some(group) = self.asset_groups.iter().find(|group| group.members.contains(&key)) {
+            return (group.name.clone(), group.peg);
+        }
+
+        if self.asset_groups.iter().any(|group| group.name == token.symbol) {
+            return (format!("{}:{:?}", token.symbol, chain), PegType::Canonical);
+        }
+
+        (token.symbol.clone(), PegType::Canonical)
+    }
+
+    pub fn add_token(&mut self, chain: Chain, token: Token) -> NodeIndex {
+        let (symbol, peg) = self.asset_for(chain, &token);
+
+        let node_index = match self.nodes_map.get(&symbol) {
+            Some(node_index) => *node_index,
+            None => {
+                let grouped = self.asset_groups.iter().any(|group| group.name == symbol);
+                if !grouped && symbol != token.symbol {
+                    warn!(
+                        "Token {} ({}) on {:?} is not in the asset group of the same name",
+                        token.symbol, token.address, chain
+                    );
+                }
+                tracing::info!("Adding token node {}", symbol);
+                let node_index = self.graph.add_node(TokenNode {
+                    symbol: symbol.clone(),
+                    peg,
+                    tokens: HashMap::new(),
+                });
+                self.nodes_map.insert(symbol, node_index);
+                node_index
+            }
+        };
+
+        self.token_nodes.insert((chain, token.address.clone()), node_index);
+        let node = self.graph.node_weight_mut(node_index).unwrap();
+        node.tokens.entry(chain).or_default().push(token);
+
+        node_index
+    }
+
+    /// Replaces the tokens tracked on `chain` with `tokens`, retiring nodes that are no longer
+    /// present on any chain. All edges and the status of the chain are dropped, the restarted
+    /// stream re-delivers them with its first snapshot.
+    pub fn sync_chain_tokens(&mut self, chain: Chain, tokens: HashMap<Bytes, Token>) {
+        self.remove_chain_edges(chain);
+        self.chain_status.remove(&chain);
+        self.token_nodes.retain(|(token_chain, _), _| *token_chain != chain);
+
+        for node_index in self.graph.node_indices().collect::<Vec<_>>() {
+            let node = self.graph.node_weight_mut(node_index).unwrap();
+            node.tokens.remove(&chain);
+
+            if node.tokens.is_empty() {
+                tracing::info!("Retiring token node {}", node.symbol);
+                self.nodes_map.remove(&node.symbol);
+                self.graph.remove_node(node_index);
             }
         }
 
-        for (symbol, tokens_map) in symbol_to_tokens {
-            let node_index = self.graph.add_node(TokenNode {
-                symbol: symbol.clone(),
-                tokens: tokens_map,
-            });
-            self.nodes_map.insert(symbol, node_index);
+        for (_, token) in tokens {
+            self.add_token(chain, token);
+        }
+    }
+
+    pub fn remove_chain_edges(&mut self, chain: Chain) {
+        let chain_edges = self
+            .graph
+            .edge_indices()
+            .filter(|idx| self.graph.edge_weight(*idx).unwrap().chain == chain)
+            .collect::<Vec<_>>();
+
+        for idx in chain_edges {
+            let edge = self.graph.remove_edge(idx).unwrap();
+            self.edges_map.remove(&edge.pool_address);
+            self.price_history.remove(&idx);
+        }
+    }
+
+    /// Index of the directed edge of `pool_address` that swaps out of `from_token`.
+    pub fn edge_index(&self, pool_address: &str, from_token: &Bytes) -> Option<EdgeIndex> {
+        self.edges_map.get(pool_address)?.iter().copied().find(|idx| {
+            self.graph
+                .edge_weight(*idx)
+                .is_some_and(|edge| &edge.from_token.address == from_token)
+        })
+    }
+
+    /// Looks up the directed edge of `pool_address` that swaps out of `from_token`.
+    pub fn find_edge(&self, pool_address: &str, from_token: &Bytes) -> Option<&PriceEdge> {
+        self.graph.edge_weight(self.edge_index(pool_address, from_token)?)
+    }
+
+    /// Variance of the edge's log price change per second over the recorded history. `None`
+    /// until at least two distinct updates were seen.
+    pub fn price_variance_per_sec(&self, idx: EdgeIndex) -> Option<f64> {
+        let history = self.price_history.get(&idx)?;
+        let (first, last) = (history.front()?, history.back()?);
+        let elapsed = last.0.duration_since(first.0).as_secs_f64();
+
+        if history.len() < 2 || elapsed <= 0.0 {
+            return None;
+        }
+
+        let squared_returns: f64 = history
+            .iter()
+            .zip(history.iter().skip(1))
+            .filter(|((_, p0), (_, p1))| *p0 > 0.0 && *p1 > 0.0)
+            .map(|((_, p0), (_, p1))| (p1 / p0).ln().powi(2))
+            .sum();
+
+        Some(squared_returns / elapsed)
+    }
+
+    fn record_price(&mut self, idx: EdgeIndex, price: f64) {
+        let history = self.price_history.entry(idx).or_default();
+
+        history.push_back((Instant::now(), price));
+        if history.len() > PRICE_HISTORY_LEN {
+            history.pop_front();
         }
     }
 
@@ -49,47 +234,57 @@ impl ArbitrageGraph {
         self.graph.update_edge_by_index(index, edge);
     }
 
-    pub fn handle_block_update(&mut self, msg: BlockUpdate, _chain: Chain) {
+    #[tracing::instrument(skip_all, fields(chain = ?timing.chain, block = timing.block))]
+    pub fn handle_block_update(&mut self, msg: BlockUpdate, timing: BlockTiming) {
         for (_, pair) in msg.new_pairs {
             let state = msg.states.get(&pair.id.to_string()).unwrap().clone();
             self.handle_new_pair(pair, state);
         }
 
         for (address, state) in msg.states {
-            self.handle_state_update(state, address);
+            self.handle_state_update(state, address, timing.block);
         }
 
-        self.format_pairs();
+        self.chain_status.insert(
+            timing.chain,
+            ChainStatus {
+                timing,
+                updated_at: Instant::now(),
+            },
+        );
     }
 
-    pub fn format_pairs(&self) {
-        let node_indices = self.graph.node_indices().collect::<Vec<_>>();
-
-        for node_index in node_indices {
-            let node_symbol = self.graph.node_weight(node_index).unwrap().symbol.clone();
-            let node_edges = self.graph.edges_directed(node_index, Incoming).collect::<Vec<_>>();
-
-            println!("{}", node_symbol);
+    /// Recomputes the flash-loan sources of the chain's tokens. Uniswap V3 sources follow the
+    /// tracked pools, so this runs again whenever pools are added or removed.
+    pub fn refresh_flash_entries(&mut self, network: &Network) {
+        self.flash_entries.retain(|(chain, _), _| *chain != network.chain);
+        if network.flash_executor.is_none() {
+            return;
+        }
 
-            for edge in node_edges {
-                let edge_weight = edge.weight().clone();
+        let tokens: Vec<Token> = self
+            .graph
+            .node_weights()
+            .filter_map(|node| node.tokens.get(&network.chain))
+            .flatten()
+            .cloned()
+            .collect();
 
-                println!(
-                    "{} -> {} | Chain: {:?} | Protocol: {} | Pool: {} | Price: {:.6}",
-                    edge_weight.from_token.symbol,
-                    edge_weight.to_token.symbol,
-                    edge_weight.chain,
-                    edge_weight.protocol.to_str(),
-                    edge_weight.pool_address,
-                    edge_weight.price
-                );
-            }
+        for token in tokens {
+            let sources = flash_sources(self, network, &token);
+            self.flash_entries.insert((network.chain, token.address), sources);
         }
     }
 
+    /// Adds both directions of the pool, each edge swapping from the token of its source node
+    /// to the token of its target node.
     pub fn handle_new_pair(&mut self, pair: ProtocolComponent, state: Box<dyn ProtocolSim>) {
-        let from_node = self.nodes_map.get(&pair.tokens[0].symbol.clone()).unwrap().clone();
-        let to_node = self.nodes_map.get(&pair.tokens[1].symbol.clone()).unwrap().clone();
+        tracing::debug!(pool = %pair.id, protocol = %pair.protocol_system, "New pair");
+
+        let from_key = (pair.chain, pair.tokens[0].address.clone());
+        let to_key = (pair.chain, pair.tokens[1].address.clone());
+        let from_node = *self.token_nodes.get(&from_key).unwrap();
+        let to_node = *self.token_nodes.get(&to_key).unwrap();
 
         let edge_index_first = self.add_edge(
             PriceEdge {
@@ -98,8 +293,9 @@ impl ArbitrageGraph {
                 price: state.spot_price(&pair.tokens[0], &pair.tokens[1]).unwrap(),
                 state: state.clone(),
                 pool_address: pair.id.to_string(),
-                to_token: pair.tokens[0].clone(),
-                from_token: pair.tokens[1].clone(),
+                component: pair.clone(),
+                to_token: pair.tokens[1].clone(),
+                from_token: pair.tokens[0].clone(),
             },
             from_node,
             to_node,
@@ -112,8 +308,9 @@ impl ArbitrageGraph {
                 price: state.spot_price(&pair.tokens[1], &pair.tokens[0]).unwrap(),
                 state: state.clone(),
                 pool_address: pair.id.to_string(),
-                to_token: pair.tokens[1].clone(),
-                from_token: pair.tokens[0].clone(),
+                component: pair.clone(),
+                to_token: pair.tokens[0].clone(),
+                from_token: pair.tokens[1].clone(),
             },
             to_node,
             from_node,
@@ -122,16 +319,21 @@ impl ArbitrageGraph {
         self.edges_map.insert(pair.id.to_string(), vec![edge_index_first, edge_index_second]);
     }
 
-    pub fn handle_state_update(&mut self, state: Box<dyn ProtocolSim>, address: String) {
+    pub fn handle_state_update(
+        &mut self,
+        state: Box<dyn ProtocolSim>,
+        address: String,
+        block: u64,
+    ) {
         if let Some(edge_indices) = self.edges_map.get(&address) {
             let indices = edge_indices.clone();
             for idx in indices {
-                self.update_edge_weight(idx, state.clone());
+                self.update_edge_weight(idx, state.clone(), block);
             }
         }
     }
 
-    pub fn update_edge_weight(&mut self, idx: EdgeIndex, state: Box<dyn ProtocolSim>) {
+    pub fn update_edge_weight(&mut self, idx: EdgeIndex, state: Box<dyn ProtocolSim>, block: u64) {
         let mut edge_weight = self.graph.edge_weight(idx).unwrap().clone();
 
         edge_weight.price = state
@@ -139,6 +341,13 @@ impl ArbitrageGraph {
             .unwrap();
         edge_weight.state = state;
 
+        self.record_price(idx, edge_weight.price);
+        if let Some(feed) = &self.feed {
+            feed.publish_price(&edge_weight);
+        }
+        if let Some(recorder) = &self.recorder {
+            recorder.record(self, &edge_weight, block);
+        }
         self.update_edge(idx, edge_weight);
     }
 }
@@ -0,0 +1,38 @@
+use crate::configuration::load_config;
+use crate::types::Config;
+use crate::utils::constants::{CONFIG_PATH, CONFIG_POLL_INTERVAL_MS};
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc::Sender;
+
+fn modified_at() -> Option<SystemTime> {
+    std::fs::metadata(CONFIG_PATH).and_then(|m| m.modified()).ok()
+}
+
+/// Polls the config file and sends every new version that passes validation.
+pub async fn watch_config(tx: Sender<Config>) {
+    let mut interval = tokio::time::interval(Duration::from_millis(CONFIG_POLL_INTERVAL_MS));
+    let mut last_modified = modified_at();
+
+    loop {
+        interval.tick().await;
+
+        let modified = modified_at();
+        if modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+
+        let config = match load_config() {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::error!("Ignoring invalid config change: {}", e);
+                continue;
+            }
+        };
+
+        tracing::info!("Config file changed, reloading");
+        if tx.send(config).await.is_err() {
+            return;
+        }
+    }
+}
@@ -1,79 +1,472 @@
-use crate::types::Config;
+use crate::types::{AssetGroup, Config, ConfigDiff, ConfigIssue, ExecutionMode, Protocol};
+use crate::stream_builder::supported_protocols;
+use crate::utils::constants::{CONFIG_PATH, network};
+use alloy::primitives::Address;
 use serde_yaml;
 use std::collections::{HashMap, HashSet};
-use std::env;
 use std::str::FromStr;
+use tycho_common::Bytes;
 use tycho_common::models::Chain;
 
 pub fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
-    let config_file = std::fs::File::open("config.yml")?;
-    let config: Config = serde_yaml::from_reader(config_file)?;
+    let config = read_config()?;
+
+    let issues = validate_config(&config);
+    if !issues.is_empty() {
+        return Err(format!("invalid config\n{}", format_issues(&issues)).into());
+    }
+
+    return Ok(config);
+}
 
-    validate_config(&config)?;
+pub fn read_config() -> Result<Config, Box<dyn std::error::Error>> {
+    let config_file = std::fs::File::open(CONFIG_PATH)?;
+    let config: Config = serde_yaml::from_reader(config_file)?;
 
     return Ok(config);
 }
 
-fn validate_config(config: &Config) -> Result<(), String> {
-    if config.private_key.is_empty() {
-        return Err("Private key is required".into());
+/// Renders issues as a two column table of YAML path and message.
+pub fn format_issues(issues: &[ConfigIssue]) -> String {
+    let width = issues.iter().map(|i| i.path.len()).max().unwrap_or(0).max("PATH".len());
+
+    let mut table = format!("{:<width$}  ISSUE\n", "PATH");
+    for issue in issues {
+        table.push_str(&format!("{:<width$}  {}\n", issue.path, issue.message));
+    }
+
+    table
+}
+
+fn validate_address(address: &str) -> Option<String> {
+    let hex = address.strip_prefix("0x").unwrap_or(address);
+
+    if !address.starts_with("0x") || hex.len() != 40 || Address::from_str(address).is_err() {
+        return Some(format!("Malformed address {}, expected 0x-prefixed 20 byte hex", address));
+    }
+
+    let mixed_case = hex.chars().any(|c| c.is_ascii_uppercase())
+        && hex.chars().any(|c| c.is_ascii_lowercase());
+    if mixed_case && Address::parse_checksummed(address, None).is_err() {
+        return Some(format!("Invalid EIP-55 checksum for address {}", address));
+    }
+
+    None
+}
+
+/// Whether the signer is needed at startup: to send transactions, or to look up the balances
+/// of tokens without a configured inventory.
+pub fn needs_signer(config: &Config) -> bool {
+    config.execution_mode == ExecutionMode::Live
+        || config.chains.iter().flat_map(|c| &c.tokens).any(|t| t.inventory.is_none())
+}
+
+/// Checks the whole config and reports every problem found instead of stopping at the first.
+pub fn validate_config(config: &Config) -> Vec<ConfigIssue> {
+    let mut issues = vec![];
+    let mut issue = |path: String, message: String| issues.push(ConfigIssue { path, message });
+
+    if config.private_key.is_empty() && needs_signer(config) {
+        issue(
+            "private_key".into(),
+            "Private key is required in live mode or when a token has no configured inventory"
+                .into(),
+        );
     }
 
     // TODO: add risk param validation
 
     if config.chains.is_empty() {
-        return Err("Chain configuration is required".into());
+        issue("chains".into(), "Chain configuration is required".into());
+    } else if config.chains.len() < 2 {
+        issue("chains".into(), "At least two chains are required".into());
+    }
+
+    let mut chain_names = HashSet::new();
+
+    for (i, chain_config) in config.chains.iter().enumerate() {
+        let path = format!("chains[{}]", i);
+
+        if !chain_names.insert(chain_config.name.as_str()) {
+            issue(format!("{path}.name"), format!("Duplicate chain: {}", chain_config.name));
+        }
+
+        match Chain::from_str(&chain_config.name) {
+            Err(_) => {
+                issue(format!("{path}.name"), format!("Unknown chain name: {}", chain_config.name));
+            }
+            Ok(chain) => {
+                if network(chain_config.name.clone()).is_none() {
+                    issue(
+                        format!("{path}.name"),
+                        format!("No network configured for chain: {}", chain_config.name),
+                    );
+                }
+
+                let supported = supported_protocols(&chain);
+                for (j, name) in chain_config.protocols.iter().enumerate() {
+                    let message = match Protocol::from_str(name) {
+                        None => format!("Unknown protocol: {}", name),
+                        Some(p) if !supported.contains(&p) => {
+                            format!("Protocol {} is not supported on {}", name, chain_config.name)
+                        }
+                        Some(_) => continue,
+                    };
+                    issue(format!("{path}.protocols[{j}]"), message);
+                }
+            }
+        }
+
+        if chain_config.rpc_url.as_deref() == Some("") {
+            issue(
+                format!("{path}.rpc_url"),
+                format!("Empty RPC URL for chain: {}", chain_config.name),
+            );
+        }
+
+        if let Some(executor) = &chain_config.flash_executor {
+            if let Some(message) = validate_address(executor) {
+                issue(format!("{path}.flash_executor"), message);
+            }
+        }
+
+        if chain_config.stale_after_blocks == Some(0) {
+            issue(
+                format!("{path}.stale_after_blocks"),
+                "Stale-after block count must be positive".into(),
+            );
+        }
     }
 
-    if config.chains.len() < 2 {
-        return Err("At least two chains are required".into());
+    for (i, bridge) in config.rebalance.bridges.iter().enumerate() {
+        for (field, name) in [("from", &bridge.from), ("to", &bridge.to)] {
+            if !config.chains.iter().any(|c| &c.name == name) {
+                issue(
+                    format!("rebalance.bridges[{i}].{field}"),
+                    format!("Bridge references unconfigured chain: {}", name),
+                );
+            }
+        }
     }
 
-    for chain_config in &config.chains {
-        if Chain::from_str(&chain_config.name).is_err() {
-            return Err(format!("Unknown chain name: {}", chain_config.name).into());
+    for (i, url) in config.alerts.webhooks.iter().enumerate() {
+        let valid = reqwest::Url::parse(url)
+            .is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+        if !valid {
+            issue(format!("alerts.webhooks[{i}]"), "Webhook must be an http(s) URL".into());
         }
     }
 
-    if !config.rpc_url.is_empty() {
-        let rpc_url = config.rpc_url.clone();
-        unsafe {
-            env::set_var("RPC_URL", rpc_url);
+    for (symbol, size) in &config.dashboard.reference_sizes {
+        if !size.is_finite() || *size <= 0.0 {
+            issue(
+                format!("dashboard.reference_sizes.{symbol}"),
+                "Reference size must be positive".into(),
+            );
         }
-    } else {
-        tracing::warn!("No RPC URL provided, some exchanges may not be available");
     }
 
-    let mut token_locations: HashMap<String, HashSet<String>> = HashMap::new();
+    // (chain, lowercase address) -> asset group name
+    let mut grouped_tokens: HashMap<(String, String), String> = HashMap::new();
 
-    // map through tokens and validate all fields are present
+    for (i, asset) in config.assets.iter().enumerate() {
+        let path = format!("assets[{}]", i);
+
+        if asset.name.is_empty() {
+            issue(format!("{path}.name"), "Name is required for asset group".into());
+        }
+
+        if asset.tokens.is_empty() {
+            issue(
+                format!("{path}.tokens"),
+                format!("At least one token is required for asset: {}", asset.name),
+            );
+        }
+
+        for (j, asset_token) in asset.tokens.iter().enumerate() {
+            let token_path = format!("{path}.tokens[{j}]");
+
+            if let Some(message) = validate_address(&asset_token.address) {
+                issue(format!("{token_path}.address"), message);
+                continue;
+            }
+
+            let chain = config.chains.iter().find(|c| c.name == asset_token.chain);
+            let listed = chain.is_some_and(|c| {
+                c.tokens
+                    .iter()
+                    .any(|t| t.address.eq_ignore_ascii_case(&asset_token.address))
+            });
+
+            if !listed {
+                issue(
+                    format!("{token_path}.address"),
+                    format!(
+                        "Asset {} references token {} not configured on chain {}",
+                        asset.name, asset_token.address, asset_token.chain
+                    ),
+                );
+            }
+
+            let key = (asset_token.chain.clone(), asset_token.address.to_lowercase());
+            if let Some(other) = grouped_tokens.insert(key, asset.name.clone()) {
+                issue(
+                    token_path,
+                    format!(
+                        "Token {} on {} belongs to both {} and {}",
+                        asset_token.address, asset_token.chain, other, asset.name
+                    ),
+                );
+            }
+        }
+    }
+
+    // symbol of a grouped token -> (its asset group, its chain)
+    let mut grouped_symbols: HashMap<&str, (&str, &str)> = HashMap::new();
     for chain in &config.chains {
+        for token in &chain.tokens {
+            let key = (chain.name.clone(), token.address.to_lowercase());
+            if let Some(group) = grouped_tokens.get(&key) {
+                grouped_symbols.insert(&token.symbol, (group, &chain.name));
+            }
+        }
+    }
+
+    // asset -> (chains it is configured on, path of its first token)
+    let mut token_locations: HashMap<String, (HashSet<String>, String)> = HashMap::new();
+
+    // map through tokens and validate all fields are present
+    for (i, chain) in config.chains.iter().enumerate() {
+        let path = format!("chains[{}].tokens", i);
+
         if chain.tokens.is_empty() {
-            return Err(format!("At least one token is required for chain: {}", chain.name).into());
+            issue(
+                path.clone(),
+                format!("At least one token is required for chain: {}", chain.name),
+            );
         }
 
-        for token in &chain.tokens {
+        let mut symbols = HashSet::new();
+        // lowercase address -> symbol of the first token using it
+        let mut addresses: HashMap<String, &str> = HashMap::new();
+
+        for (j, token) in chain.tokens.iter().enumerate() {
+            let token_path = format!("{path}[{j}]");
+
             if token.symbol.is_empty() {
-                return Err(format!("Symbol is required for token: {}", token.address).into());
+                issue(
+                    format!("{token_path}.symbol"),
+                    format!("Symbol is required for token: {}", token.address),
+                );
+            } else if !symbols.insert(token.symbol.as_str()) {
+                issue(
+                    format!("{token_path}.symbol"),
+                    format!("Duplicate symbol {} on chain {}", token.symbol, chain.name),
+                );
             }
 
             if token.address.is_empty() {
-                return Err(format!("Address is required for token: {}", token.symbol).into());
+                issue(
+                    format!("{token_path}.address"),
+                    format!("Address is required for token: {}", token.symbol),
+                );
+            } else if let Some(message) = validate_address(&token.address) {
+                issue(format!("{token_path}.address"), message);
+            } else if let Some(other) =
+                addresses.insert(token.address.to_lowercase(), token.symbol.as_str())
+            {
+                issue(
+                    format!("{token_path}.address"),
+                    format!(
+                        "Duplicate address {} on chain {}, already used by {}",
+                        token.address, chain.name, other
+                    ),
+                );
+            }
+
+            let group = grouped_tokens.get(&(chain.name.clone(), token.address.to_lowercase()));
+            let asset = group.unwrap_or(&token.symbol);
+
+            // ungrouped tokens are merged by symbol, which must not silently join or split
+            // from an asset group
+            if group.is_none() {
+                let grouped_elsewhere = grouped_symbols
+                    .get(token.symbol.as_str())
+                    .filter(|(_, other_chain)| *other_chain != chain.name);
+
+                if config.assets.iter().any(|a| a.name == token.symbol) {
+                    issue(
+                        format!("{token_path}.symbol"),
+                        format!(
+                            "Token {} on {} is not in asset group {} but shares its name, add \
+                             it to the group or rename the group",
+                            token.symbol, chain.name, token.symbol
+                        ),
+                    );
+                } else if let Some((group, other_chain)) = grouped_elsewhere {
+                    issue(
+                        format!("{token_path}.symbol"),
+                        format!(
+                            "Token {} on {} is not in asset group {} like {} on {}, add it to \
+                             the group or it trades as a separate asset",
+                            token.symbol, chain.name, group, token.symbol, other_chain
+                        ),
+                    );
+                }
             }
 
             token_locations
-                .entry(token.symbol.clone())
-                .or_insert(HashSet::new())
+                .entry(asset.clone())
+                .or_insert((HashSet::new(), token_path))
+                .0
                 .insert(chain.name.clone());
         }
     }
 
-    // validate that each token is present on multiple chains
-    for (symbol, chains) in &token_locations {
-        if chains.len() < 2 {
-            return Err(format!("Token {} is not present on multiple chains", symbol).into());
+    // validate that each asset is present on multiple chains
+    let mut single_chain = token_locations
+        .into_iter()
+        .filter(|(_, (chains, _))| chains.len() < 2)
+        .collect::<Vec<_>>();
+    single_chain.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (asset, (_, path)) in single_chain {
+        issue(path, format!("Token {} is not present on multiple chains", asset));
+    }
+
+    issues
+}
+
+pub fn asset_groups(config: &Config) -> Vec<AssetGroup> {
+    config
+        .assets
+        .iter()
+        .map(|asset| AssetGroup {
+            name: asset.name.clone(),
+            peg: asset.peg,
+            members: asset
+                .tokens
+                .iter()
+                .map(|t| {
+                    (
+                        Chain::from_str(&t.chain).unwrap(),
+                        Bytes::from_str(&t.address).unwrap(),
+                    )
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Per-token inventory targets in token units.
+pub fn inventory_targets(config: &Config) -> HashMap<(Chain, Bytes), f64> {
+    let mut targets = HashMap::new();
+
+    for chain_config in &config.chains {
+        let chain = Chain::from_str(&chain_config.name).unwrap();
+
+        for token in &chain_config.tokens {
+            if let Some(target) = token.target_inventory {
+                targets.insert((chain, Bytes::from_str(&token.address).unwrap()), target);
+            }
+        }
+    }
+
+    targets
+}
+
+/// Compares a freshly loaded config against the running one. Chains whose token list or RPC
+/// changed are returned in full so their streams can be rebuilt; adding or removing chains
+/// still requires a restart.
+pub fn diff_config(current: &Config, new: &Config) -> ConfigDiff {
+    let mut diff = ConfigDiff::default();
+
+    if current.assets != new.assets {
+        tracing::warn!("Asset groups changed, restart required");
+    }
+
+    if current.private_key != new.private_key {
+        tracing::warn!("Private key changed, restart required");
+    }
+
+    if current.journal != new.journal {
+        tracing::warn!("Journal config changed, restart required");
+    }
+
+    if current.logging != new.logging {
+        tracing::warn!("Logging config changed, restart required");
+    }
+
+    if current.recorder != new.recorder {
+        tracing::warn!("Recorder config changed, restart required");
+    }
+
+    if current.alerts != new.alerts {
+        tracing::warn!("Alerts config changed, restart required");
+    }
+
+    if current.dashboard != new.dashboard {
+        tracing::warn!("Dashboard config changed, restart required");
+    }
+
+    if current.risk_param != new.risk_param {
+        diff.risk_param = Some(new.risk_param);
+    }
+
+    for new_chain in &new.chains {
+        match current.chains.iter().find(|c| c.name == new_chain.name) {
+            Some(current_chain) if current_chain != new_chain => {
+                if current_chain.transfer_mode != new_chain.transfer_mode {
+                    tracing::warn!(
+                        "Transfer mode of chain {} changed, restart required",
+                        new_chain.name
+                    );
+                }
+                if current_chain.flash_executor != new_chain.flash_executor {
+                    tracing::warn!(
+                        "Flash executor of chain {} changed, restart required",
+                        new_chain.name
+                    );
+                }
+                if current_chain.rpc_url != new_chain.rpc_url {
+                    tracing::warn!(
+                        "RPC URL of chain {} changed, transactions keep the old one until restart",
+                        new_chain.name
+                    );
+                }
+                if current_chain.stale_after_blocks != new_chain.stale_after_blocks {
+                    tracing::warn!(
+                        "Stale-after blocks of chain {} changed, restart required",
+                        new_chain.name
+                    );
+                }
+                diff.reloaded_chains.push(new_chain.clone());
+            }
+            Some(_) => {}
+            None => {
+                tracing::warn!("Chain {} added to config, restart required", new_chain.name);
+            }
+        }
+    }
+
+    for current_chain in &current.chains {
+        if !new.chains.iter().any(|c| c.name == current_chain.name) {
+            tracing::warn!("Chain {} removed from config, restart required", current_chain.name);
         }
     }
 
-    return Ok(());
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_config_is_valid() {
+        let config: Config = serde_yaml::from_str(include_str!("../config.example.yml")).unwrap();
+
+        assert!(!needs_signer(&config));
+        assert_eq!(format_issues(&validate_config(&config)), format_issues(&[]));
+    }
 }
@@ -0,0 +1,313 @@
+use crate::health::is_stale;
+use crate::metrics::{STREAM_ERRORS, chain_label};
+use crate::types::{FeedEvent, GraphSnapshot, Network, OpportunityView, Symbol};
+use crate::utils::constants::{DASHBOARD_MAX_OPPORTUNITIES, DASHBOARD_REFRESH_MS};
+use ratatui::DefaultTerminal;
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::sync::watch;
+use tycho_common::models::Chain;
+
+struct ChainRow {
+    name: String,
+    block: Option<u64>,
+    age: Option<Duration>,
+    errors: u64,
+    healthy: bool,
+}
+
+/// What one frame shows, taken from a single published graph snapshot.
+struct Snapshot {
+    chains: Vec<ChainRow>,
+    symbol: Option<Symbol>,
+    quote: Option<Symbol>,
+    /// Reference sizes of the symbol and the quote, the matrix is empty without both
+    sizes: Option<(f64, f64)>,
+    /// `matrix[buy][sell]`: spread in bps of buying the symbol on one chain and selling it on
+    /// another, from executable prices at the reference sizes, after price impact and fees
+    matrix: Vec<Vec<Option<f64>>>,
+}
+
+struct Dashboard {
+    graph: watch::Receiver<Arc<GraphSnapshot>>,
+    reference_sizes: HashMap<Symbol, f64>,
+    networks: Vec<Network>,
+    events: broadcast::Receiver<FeedEvent>,
+    opportunities: VecDeque<OpportunityView>,
+    symbol_index: isize,
+    quote_index: isize,
+}
+
+/// Runs the terminal dashboard on the calling thread until the operator quits. It blocks on
+/// terminal input, so give it a thread of its own.
+pub fn run_dashboard(
+    graph: watch::Receiver<Arc<GraphSnapshot>>,
+    reference_sizes: HashMap<Symbol, f64>,
+    networks: Vec<Network>,
+    events: broadcast::Receiver<FeedEvent>,
+) -> Result<(), anyhow::Error> {
+    let mut dashboard = Dashboard {
+        graph,
+        reference_sizes,
+        networks,
+        events,
+        opportunities: VecDeque::new(),
+        symbol_index: 0,
+        quote_index: 0,
+    };
+
+    let mut terminal = ratatui::init();
+    let result = dashboard.run(&mut terminal);
+    ratatui::restore();
+
+    result
+}
+
+impl Dashboard {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), anyhow::Error> {
+        loop {
+            self.drain_events();
+            let snapshot = self.snapshot();
+            terminal.draw(|frame| self.draw(frame, &snapshot))?;
+
+            if !event::poll(Duration::from_millis(DASHBOARD_REFRESH_MS))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Right => self.symbol_index += 1,
+                KeyCode::Left => self.symbol_index -= 1,
+                KeyCode::Down => self.quote_index += 1,
+                KeyCode::Up => self.quote_index -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    fn drain_events(&mut self) {
+        loop {
+            match self.events.try_recv() {
+                Ok(FeedEvent::Opportunity(opportunity)) => {
+                    self.opportunities.push_front(opportunity);
+                    self.opportunities.truncate(DASHBOARD_MAX_OPPORTUNITIES);
+                }
+                Ok(_) | Err(TryRecvError::Lagged(_)) => {}
+                Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => break,
+            }
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let graph = Arc::clone(&self.graph.borrow());
+
+        let chains = self
+            .networks
+            .iter()
+            .map(|network| {
+                let status = graph.chain_status.get(&network.chain);
+
+                ChainRow {
+                    name: network.name.clone(),
+                    block: status.map(|status| status.timing.block),
+                    age: status.map(|status| status.updated_at.elapsed()),
+                    errors: STREAM_ERRORS
+                        .with_label_values(&[&chain_label(network.chain)])
+                        .get(),
+                    healthy: status.is_some_and(|status| !is_stale(status, network)),
+                }
+            })
+            .collect();
+
+        let symbols = graph.nodes_map.keys().cloned().collect::<BTreeSet<_>>();
+        let symbol = pick(&symbols, self.symbol_index);
+
+        let mut quotes = BTreeSet::new();
+        // best executable price received for `symbol` in `quote` per chain, and best one paid
+        let mut bids: HashMap<Chain, f64> = HashMap::new();
+        let mut asks: HashMap<Chain, f64> = HashMap::new();
+
+        if let Some(symbol) = &symbol {
+            for edge in graph.graph.edge_weights().filter(|edge| &edge.from_asset == symbol) {
+                quotes.insert(edge.to_asset.clone());
+            }
+        }
+        let quote = pick(&quotes, self.quote_index);
+
+        let sizes = match (&symbol, &quote) {
+            (Some(symbol), Some(quote)) => {
+                self.reference_sizes.get(symbol).zip(self.reference_sizes.get(quote))
+            }
+            _ => None,
+        };
+
+        if let (Some(symbol), Some(quote), Some(_)) = (&symbol, &quote, sizes) {
+            for edge in graph.graph.edge_weights() {
+                let Some(price) = edge.executable_price.filter(|price| *price > 0.0) else {
+                    continue;
+                };
+
+                if &edge.from_asset == symbol && &edge.to_asset == quote {
+                    let bid = bids.entry(edge.view.chain).or_insert(0.0);
+                    *bid = bid.max(price);
+                } else if &edge.from_asset == quote && &edge.to_asset == symbol {
+                    let ask = asks.entry(edge.view.chain).or_insert(f64::INFINITY);
+                    *ask = ask.min(1.0 / price);
+                }
+            }
+        }
+
+        let matrix = self
+            .networks
+            .iter()
+            .map(|buy| {
+                self.networks
+                    .iter()
+                    .map(|sell| {
+                        let ask = asks.get(&buy.chain)?;
+                        let bid = bids.get(&sell.chain)?;
+                        Some((bid / ask - 1.0) * 10_000.0)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Snapshot {
+            chains,
+            symbol,
+            quote,
+            sizes: sizes.map(|(symbol_size, quote_size)| (*symbol_size, *quote_size)),
+            matrix,
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame, snapshot: &Snapshot) {
+        let chain_rows = self.networks.len() as u16;
+        let [header, chains, matrix, opportunities] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(chain_rows + 3),
+            Constraint::Length(chain_rows + 3),
+            Constraint::Min(3),
+        ])
+        .areas(frame.area());
+
+        frame.render_widget(
+            Paragraph::new(Line::from(
+                "multiarb | <-/-> symbol | up/down quote | q quit".bold(),
+            )),
+            header,
+        );
+        frame.render_widget(self.chains_table(snapshot), chains);
+        frame.render_widget(self.matrix_table(snapshot), matrix);
+        frame.render_widget(self.opportunities_table(), opportunities);
+    }
+
+    fn chains_table(&self, snapshot: &Snapshot) -> Table<'static> {
+        let rows = snapshot.chains.iter().map(|chain| {
+            let health = if chain.healthy {
+                Cell::from("ok").style(Style::default().fg(Color::Green))
+            } else {
+                Cell::from("stale").style(Style::default().fg(Color::Red))
+            };
+
+            Row::new(vec![
+                Cell::from(chain.name.clone()),
+                Cell::from(chain.block.map(|b| b.to_string()).unwrap_or("-".into())),
+                Cell::from(
+                    chain
+                        .age
+                        .map(|age| format!("{:.1}s", age.as_secs_f64()))
+                        .unwrap_or("-".into()),
+                ),
+                Cell::from(chain.errors.to_string()),
+                health,
+            ])
+        });
+
+        Table::new(rows, [Constraint::Length(12); 5])
+            .header(Row::new(["chain", "block", "last update", "errors", "stream"]).bold())
+            .block(Block::bordered().title("Streams"))
+    }
+
+    fn matrix_table(&self, snapshot: &Snapshot) -> Table<'static> {
+        let title = match (&snapshot.symbol, &snapshot.quote, snapshot.sizes) {
+            (Some(symbol), Some(quote), Some((symbol_size, quote_size))) => format!(
+                "{}/{} spread bps at {} {} and {} {}, buy (row) -> sell (column)",
+                symbol, quote, symbol_size, symbol, quote_size, quote
+            ),
+            (Some(symbol), Some(quote), None) => format!(
+                "{}/{}: no dashboard.reference_sizes configured for both assets",
+                symbol, quote
+            ),
+            _ => "No prices yet".to_string(),
+        };
+
+        let header = Row::new(
+            std::iter::once(String::new()).chain(self.networks.iter().map(|n| n.name.clone())),
+        )
+        .bold();
+
+        let rows = self.networks.iter().zip(&snapshot.matrix).map(|(buy, cells)| {
+            let cells = cells.iter().map(|spread| match spread {
+                Some(spread) => {
+                    let color = if *spread > 0.0 { Color::Green } else { Color::Gray };
+                    Cell::from(format!("{:+.1}", spread)).style(Style::default().fg(color))
+                }
+                None => Cell::from("-"),
+            });
+
+            Row::new(std::iter::once(Cell::from(buy.name.clone()).bold()).chain(cells))
+        });
+
+        let widths = vec![Constraint::Length(12); self.networks.len() + 1];
+        Table::new(rows, widths).header(header).block(Block::bordered().title(title))
+    }
+
+    fn opportunities_table(&self) -> Table<'static> {
+        let rows = self.opportunities.iter().map(|opportunity| {
+            let legs = opportunity
+                .legs
+                .iter()
+                .map(|leg| format!("{}: {}", chain_label(leg.chain), leg.path.join(" > ")))
+                .collect::<Vec<_>>()
+                .join(" | ");
+            let tolerance = opportunity
+                .legs
+                .iter()
+                .map(|leg| leg.tolerance_bps.to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            Row::new(vec![opportunity.id.clone(), legs, tolerance])
+        });
+
+        let widths = [Constraint::Length(24), Constraint::Min(40), Constraint::Length(14)];
+        Table::new(rows, widths)
+            .header(Row::new(["id", "legs", "tolerance bps"]).bold())
+            .block(Block::bordered().title("Latest opportunities"))
+    }
+}
+
+/// Item at `index` modulo the set size, so the selection wraps both ways.
+fn pick(items: &BTreeSet<Symbol>, index: isize) -> Option<Symbol> {
+    if items.is_empty() {
+        return None;
+    }
+
+    items.iter().nth(index.rem_euclid(items.len() as isize) as usize).cloned()
+}
@@ -0,0 +1,340 @@
+use crate::flash_loan::locks_pool;
+use crate::inventory::InventoryManager;
+use crate::metrics::chain_label;
+use crate::types::{
+    ArbitrageGraph, BlockTiming, FlashLoanSource, Opportunity, OpportunityLeg, PriceEdge, SwapHop,
+};
+use crate::utils::constants::{
+    DETECTION_MAX_CYCLES, DETECTION_MAX_HOPS, DETECTION_MIN_RETURN, FLASH_SIZING_STEPS,
+    SIZING_STEPS,
+};
+use crate::utils::units::{rescale, to_units};
+use num_bigint::BigUint;
+use petgraph::prelude::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tycho_common::models::Chain;
+
+static NEXT_OPPORTUNITY: AtomicU64 = AtomicU64::new(0);
+
+/// A cycle of pools whose spot prices multiply to more than one. The edges are cloned so the
+/// cycle can be sized after the graph lock is released.
+#[derive(Debug, Clone)]
+pub struct Cycle {
+    pub edges: Vec<PriceEdge>,
+    /// Product of the spot prices along the cycle
+    pub spot_return: f64,
+    /// Zero-capital entry of a cycle that stays on a chain with a flash executor: the cheapest
+    /// flash-loan source of the first token that the cycle does not swap through
+    pub flash_entry: Option<FlashLoanSource>,
+}
+
+/// Cycles of up to `DETECTION_MAX_HOPS` pools that go through at least one pool on `chain` and
+/// return at least `DETECTION_MIN_RETURN` at spot prices, best first. Each cycle is found once,
+/// from its lowest node index. Consecutive pools on different chains hand the asset over
+/// between chains, which the inventory on both sides covers.
+pub fn detect_cycles(graph: &ArbitrageGraph, chain: Chain) -> Vec<Cycle> {
+    let mut cycles = vec![];
+
+    for start in graph.graph.node_indices() {
+        let mut path = vec![];
+        extend_path(graph, chain, start, start, 1.0, &mut path, &mut cycles);
+    }
+
+    cycles.sort_by(|a, b| b.spot_return.total_cmp(&a.spot_return));
+    cycles.truncate(DETECTION_MAX_CYCLES);
+
+    for cycle in &mut cycles {
+        enter_with_flash_loan(graph, cycle);
+    }
+
+    cycles
+}
+
+/// Rotates a single-chain cycle to start at the token with the cheapest usable flash-loan
+/// source, which then funds the cycle instead of inventory.
+fn enter_with_flash_loan(graph: &ArbitrageGraph, cycle: &mut Cycle) {
+    let chain = cycle.edges[0].chain;
+    if cycle.edges.iter().any(|edge| edge.chain != chain) {
+        return;
+    }
+
+    let usable = |source: &&FlashLoanSource| {
+        !cycle.edges.iter().any(|edge| locks_pool(source, edge.protocol, &edge.component))
+    };
+    let entry = cycle
+        .edges
+        .iter()
+        .enumerate()
+        .filter_map(|(i, edge)| {
+            let key = (chain, edge.from_token.address.clone());
+            let sources = graph.flash_entries.get(&key)?;
+            let source = sources.iter().filter(usable).min_by_key(|source| source.fee_pips)?;
+            Some((i, source.clone()))
+        })
+        .min_by_key(|(_, source)| source.fee_pips);
+
+    if let Some((start, source)) = entry {
+        cycle.edges.rotate_left(start);
+        cycle.flash_entry = Some(source);
+    }
+}
+
+fn extend_path(
+    graph: &ArbitrageGraph,
+    chain: Chain,
+    start: NodeIndex,
+    node: NodeIndex,
+    spot_return: f64,
+    path: &mut Vec<EdgeIndex>,
+    cycles: &mut Vec<Cycle>,
+) {
+    for edge in graph.graph.edges(node) {
+        let weight = edge.weight();
+        let reused = path.iter().any(|idx| graph.graph[*idx].pool_address == weight.pool_address);
+        if weight.price <= 0.0 || reused {
+            continue;
+        }
+
+        let spot_return = spot_return * weight.price;
+        let target = edge.target();
+
+        if target == start {
+            let edges = path.iter().map(|idx| &graph.graph[*idx]).chain([weight]);
+            let touches_chain = edges.clone().any(|edge| edge.chain == chain);
+
+            if !path.is_empty() && touches_chain && spot_return >= DETECTION_MIN_RETURN {
+                cycles.push(Cycle {
+                    edges: edges.cloned().collect(),
+                    spot_return,
+                    flash_entry: None,
+                });
+            }
+            continue;
+        }
+
+        let visited = path
+            .iter()
+            .any(|idx| graph.graph.edge_endpoints(*idx).is_some_and(|(_, to)| to == target));
+        if target < start || visited || path.len() + 1 >= DETECTION_MAX_HOPS {
+            continue;
+        }
+
+        path.push(edge.id());
+        extend_path(graph, chain, start, target, spot_return, path, cycles);
+        path.pop();
+    }
+}
+
+/// Splits the cycle into legs of consecutive pools on one chain. A multi-chain cycle is started
+/// at a chain switch so no chain ends up with two legs.
+fn leg_edges(cycle: &Cycle) -> Vec<Vec<&PriceEdge>> {
+    let len = cycle.edges.len();
+    let start = (0..len)
+        .find(|i| cycle.edges[*i].chain != cycle.edges[(i + len - 1) % len].chain)
+        .unwrap_or(0);
+
+    let mut legs: Vec<Vec<&PriceEdge>> = vec![];
+    for i in 0..len {
+        let edge = &cycle.edges[(start + i) % len];
+        match legs.last_mut() {
+            Some(leg) if leg[0].chain == edge.chain => leg.push(edge),
+            _ => legs.push(vec![edge]),
+        }
+    }
+
+    legs
+}
+
+/// Runs the legs with `amount_in` entering the first one. Every later leg takes the previous
+/// leg's output, converted to its own token's precision and, unless the input is borrowed,
+/// capped by the inventory available on its chain.
+fn simulate_legs(
+    legs: &[Vec<&PriceEdge>],
+    inventory: Option<&InventoryManager>,
+    amount_in: BigUint,
+) -> Option<Vec<OpportunityLeg>> {
+    let mut desired = amount_in;
+    let mut previous_decimals = legs.first()?.first()?.from_token.decimals;
+    let mut result = vec![];
+
+    for edges in legs {
+        let first = edges.first()?;
+        let desired_in = rescale(&desired, previous_decimals, first.from_token.decimals);
+        let token = &first.from_token.address;
+        let mut amount = match inventory {
+            Some(inventory) => inventory.cap_amount_in(first.chain, token, desired_in),
+            None => desired_in,
+        };
+        if amount == BigUint::default() {
+            return None;
+        }
+
+        let mut hops = vec![];
+        for edge in edges {
+            let amount_out = edge
+                .state
+                .get_amount_out(amount.clone(), &edge.from_token, &edge.to_token)
+                .ok()?
+                .amount;
+
+            hops.push(SwapHop {
+                pool_address: edge.pool_address.clone(),
+                protocol: edge.protocol,
+                component: edge.component.clone(),
+                from_token: edge.from_token.clone(),
+                to_token: edge.to_token.clone(),
+                amount_in: amount,
+                amount_out: amount_out.clone(),
+            });
+            amount = amount_out;
+        }
+
+        desired = amount;
+        previous_decimals = edges.last()?.to_token.decimals;
+        result.push(OpportunityLeg {
+            chain: first.chain,
+            hops,
+            tolerance_bps: 0,
+            min_amount_out: BigUint::default(),
+        });
+    }
+
+    Some(result)
+}
+
+/// Output of the last leg minus input of the first and the flash-loan fee, in units of the
+/// asset the cycle starts and ends with.
+fn cycle_profit(legs: &[OpportunityLeg], fee_pips: u32) -> Option<f64> {
+    let first = legs.first()?.hops.first()?;
+    let last = legs.last()?.hops.last()?;
+    let fee = &first.amount_in * fee_pips / 1_000_000u32;
+
+    Some(
+        to_units(&last.amount_out, last.to_token.decimals)
+            - to_units(&(&first.amount_in + fee), first.from_token.decimals),
+    )
+}
+
+/// Sizes the cycle: the first leg tries a base amount and its successive halvings, and the
+/// most profitable size on the cloned pool states is kept. The base is the available balance
+/// of the input token, or the most the first pool takes when the input is flash-borrowed.
+/// `None` when no size is profitable.
+pub fn size_cycle(
+    cycle: &Cycle,
+    inventory: &InventoryManager,
+    timing: BlockTiming,
+) -> Option<Opportunity> {
+    let legs = leg_edges(cycle);
+    let entry = legs.first()?.first()?;
+
+    let (base, steps, fee_pips) = match &cycle.flash_entry {
+        Some(source) => {
+            let from = entry.from_token.address.clone();
+            let (max_in, _) = entry.state.get_limits(from, entry.to_token.address.clone()).ok()?;
+            (max_in, FLASH_SIZING_STEPS, source.fee_pips)
+        }
+        None => {
+            let available = inventory.available(entry.chain, &entry.from_token.address);
+            (available, SIZING_STEPS, 0)
+        }
+    };
+    let inventory = cycle.flash_entry.is_none().then_some(inventory);
+
+    let (_, legs) = (0..steps)
+        .map(|step| &base >> step)
+        .filter(|amount| *amount > BigUint::default())
+        .filter_map(|amount| simulate_legs(&legs, inventory, amount))
+        .filter_map(|legs| Some((cycle_profit(&legs, fee_pips)?, legs)))
+        .filter(|(profit, _)| *profit > 0.0)
+        .max_by(|a, b| a.0.total_cmp(&b.0))?;
+
+    let id = format!(
+        "{}-{}-{}",
+        chain_label(timing.chain),
+        timing.block,
+        NEXT_OPPORTUNITY.fetch_add(1, Ordering::Relaxed)
+    );
+
+    Some(Opportunity {
+        id,
+        legs,
+        timing,
+        flash: cycle.flash_entry.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::U256;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use tycho_common::Bytes;
+    use tycho_simulation::evm::protocol::uniswap_v2::state::UniswapV2State;
+    use tycho_simulation::models::Token;
+    use tycho_simulation::protocol::models::ProtocolComponent;
+
+    fn token(address: &str, symbol: &str, decimals: usize) -> Token {
+        Token {
+            address: Bytes::from_str(address).unwrap(),
+            decimals,
+            symbol: symbol.to_string(),
+            gas: BigUint::default(),
+        }
+    }
+
+    fn pool(id: &str, tokens: &[Token]) -> ProtocolComponent {
+        ProtocolComponent::new(
+            Bytes::from_str(id).unwrap(),
+            "uniswap_v2".to_string(),
+            "uniswap_v2_pool".to_string(),
+            Chain::Ethereum,
+            tokens.to_vec(),
+            vec![],
+            HashMap::new(),
+            Bytes::default(),
+            Default::default(),
+        )
+    }
+
+    #[test]
+    fn two_pool_cycle_swaps_chain_into_each_other() {
+        let weth = token("0x0000000000000000000000000000000000000001", "WETH", 18);
+        let usdc = token("0x0000000000000000000000000000000000000002", "USDC", 6);
+        let tokens = [weth.clone(), usdc.clone()];
+
+        let mut graph = ArbitrageGraph::new(vec![]);
+        graph.add_token(Chain::Ethereum, weth.clone());
+        graph.add_token(Chain::Ethereum, usdc.clone());
+
+        // WETH trades at 2000 USDC in the first pool and 2100 USDC in the second
+        let weth_reserve = U256::from(1_000u128 * 10u128.pow(18));
+        for (id, usdc_reserve) in [("0xa1", 2_000_000u128), ("0xa2", 2_100_000u128)] {
+            let state = UniswapV2State::new(weth_reserve, U256::from(usdc_reserve * 1_000_000));
+            graph.handle_new_pair(pool(id, &tokens), Box::new(state));
+        }
+
+        let node = |token: &Token| graph.token_nodes[&(Chain::Ethereum, token.address.clone())];
+        for edge in graph.graph.edge_indices() {
+            let (from, to) = graph.graph.edge_endpoints(edge).unwrap();
+            assert_eq!(node(&graph.graph[edge].from_token), from);
+            assert_eq!(node(&graph.graph[edge].to_token), to);
+        }
+
+        let cycles = detect_cycles(&graph, Chain::Ethereum);
+        let cycle = cycles.first().expect("no cycle detected");
+        let legs = leg_edges(cycle);
+        let amount_in = BigUint::from(10u64).pow(18);
+        let legs = simulate_legs(&legs, None, amount_in.clone()).expect("simulation failed");
+
+        let hops = legs.iter().flat_map(|leg| &leg.hops).collect::<Vec<_>>();
+        assert_eq!(hops.len(), 2);
+        assert_eq!(hops[0].from_token.address, weth.address);
+        assert_eq!(hops[0].to_token.address, usdc.address);
+        assert_eq!(hops[1].from_token.address, usdc.address);
+        assert_eq!(hops[1].to_token.address, weth.address);
+        assert_eq!(hops[1].amount_in, hops[0].amount_out);
+        assert!(hops[1].amount_out > amount_in);
+    }
+}
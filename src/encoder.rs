@@ -0,0 +1,236 @@
+use crate::approvals::permit_single;
+use crate::types::{EncodedLeg, Network, OpportunityLeg, SignedPermit, TransferMode};
+use crate::utils::rpc::create_signer;
+use crate::utils::units::to_u256;
+use alloy::primitives::{Address, U256, keccak256};
+use alloy::sol_types::SolValue;
+use num_bigint::BigUint;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tycho_common::Bytes;
+use tycho_common::models::Chain;
+use tycho_execution::encoding::evm::encoder_builders::TychoRouterEncoderBuilder;
+use tycho_execution::encoding::models::{EncodedSolution, Solution, Swap, UserTransferType};
+use tycho_execution::encoding::tycho_encoder::TychoEncoder;
+
+/// Router encoders of one chain. Swaps paid by the signer use the chain's transfer mode, swaps
+/// paid by a contract use `transferFrom` against the contract's own approval.
+struct ChainEncoder {
+    transfer_mode: TransferMode,
+    transfer_from: Box<dyn TychoEncoder>,
+    permit2: Option<Box<dyn TychoEncoder>>,
+}
+
+/// Encodes opportunity legs into Tycho router calls, one encoder per chain.
+pub struct SwapEncoder {
+    encoders: HashMap<Chain, ChainEncoder>,
+    sender: Bytes,
+}
+
+fn build_encoder(
+    network: &Network,
+    transfer_type: UserTransferType,
+) -> Result<Box<dyn TychoEncoder>, anyhow::Error> {
+    TychoRouterEncoderBuilder::new()
+        .chain(network.chain)
+        .router_address(Bytes::from_str(&network.router)?)
+        .user_transfer_type(transfer_type)
+        .build()
+        .map_err(|e| anyhow::anyhow!("Failed to build encoder for {}: {:?}", network.name, e))
+}
+
+impl SwapEncoder {
+    pub fn new(networks: &[Network], private_key: &str) -> Result<Self, anyhow::Error> {
+        let signer = create_signer(private_key)?;
+        let sender = Bytes::from(signer.address().to_vec());
+
+        let mut encoders = HashMap::new();
+
+        for network in networks {
+            let permit2 = match network.transfer_mode {
+                TransferMode::Approval => None,
+                TransferMode::Permit2 => {
+                    Some(build_encoder(network, UserTransferType::TransferFromPermit2)?)
+                }
+            };
+
+            encoders.insert(
+                network.chain,
+                ChainEncoder {
+                    transfer_mode: network.transfer_mode,
+                    transfer_from: build_encoder(network, UserTransferType::TransferFrom)?,
+                    permit2,
+                },
+            );
+        }
+
+        Ok(Self { encoders, sender })
+    }
+
+    pub fn transfer_mode(&self, chain: Chain) -> Option<TransferMode> {
+        self.encoders.get(&chain).map(|encoder| encoder.transfer_mode)
+    }
+
+    fn chain_encoder(&self, chain: Chain) -> Result<&ChainEncoder, anyhow::Error> {
+        self.encoders
+            .get(&chain)
+            .ok_or_else(|| anyhow::anyhow!("No encoder for chain {:?}", chain))
+    }
+
+    /// Encodes the hops of `leg` as one multi-hop router swap paid by the signer. Every hop goes
+    /// through the exact pool picked by the sizing step and swaps the full output of the
+    /// previous one. Chains in Permit2 mode need the signer's permit for the input token.
+    pub fn encode_leg(
+        &self,
+        leg: &OpportunityLeg,
+        min_amount_out: BigUint,
+        permit: Option<&SignedPermit>,
+    ) -> Result<EncodedLeg, anyhow::Error> {
+        let chain_encoder = self.chain_encoder(leg.chain)?;
+        let solution = solution(leg, min_amount_out, self.sender.clone())?;
+
+        let Some(encoder) = &chain_encoder.permit2 else {
+            return encode_full(chain_encoder.transfer_from.as_ref(), leg, solution);
+        };
+        let permit =
+            permit.ok_or_else(|| anyhow::anyhow!("No permit for the input of {:?}", leg.chain))?;
+
+        let encoded = encoder
+            .encode_solutions(vec![solution.clone()])
+            .map_err(|e| anyhow::anyhow!("Failed to encode leg: {:?}", e))?
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("Encoder returned no solution"))?;
+
+        let data = permit2_calldata(&encoded, &solution, permit);
+
+        Ok(EncodedLeg {
+            chain: leg.chain,
+            to: encoded.interacting_with,
+            value: BigUint::default(),
+            data,
+        })
+    }
+
+    /// Same as `encode_leg`, with `account` paying the input and receiving the output instead
+    /// of the signer, e.g. a flash-loan executor contract. The account approves the router
+    /// itself, so this always uses `transferFrom`.
+    pub fn encode_leg_for(
+        &self,
+        leg: &OpportunityLeg,
+        min_amount_out: BigUint,
+        account: Bytes,
+    ) -> Result<EncodedLeg, anyhow::Error> {
+        let chain_encoder = self.chain_encoder(leg.chain)?;
+        let solution = solution(leg, min_amount_out, account)?;
+
+        encode_full(chain_encoder.transfer_from.as_ref(), leg, solution)
+    }
+}
+
+fn solution(
+    leg: &OpportunityLeg,
+    min_amount_out: BigUint,
+    account: Bytes,
+) -> Result<Solution, anyhow::Error> {
+    let (first, last) = match (leg.hops.first(), leg.hops.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Err(anyhow::anyhow!("Cannot encode a leg without hops")),
+    };
+
+    let swaps = leg
+        .hops
+        .iter()
+        .map(|hop| {
+            Swap::new(
+                hop.component.clone().into(),
+                hop.from_token.address.clone(),
+                hop.to_token.address.clone(),
+                0f64,
+            )
+        })
+        .collect();
+
+    Ok(Solution {
+        sender: account.clone(),
+        receiver: account,
+        given_token: first.from_token.address.clone(),
+        given_amount: leg.amount_in(),
+        checked_token: last.to_token.address.clone(),
+        exact_out: false,
+        checked_amount: min_amount_out,
+        swaps,
+        ..Default::default()
+    })
+}
+
+fn encode_full(
+    encoder: &dyn TychoEncoder,
+    leg: &OpportunityLeg,
+    solution: Solution,
+) -> Result<EncodedLeg, anyhow::Error> {
+    let transaction = encoder
+        .encode_full_calldata(vec![solution])
+        .map_err(|e| anyhow::anyhow!("Failed to encode leg: {:?}", e))?
+        .pop()
+        .ok_or_else(|| anyhow::anyhow!("Encoder returned no transaction"))?;
+
+    Ok(EncodedLeg {
+        chain: leg.chain,
+        to: transaction.to,
+        value: transaction.value,
+        data: transaction.data,
+    })
+}
+
+/// Router call for an encoded solution paid through Permit2. The router's `*Permit2` entry
+/// points take the arguments of their `transferFrom` counterparts with the permit and its
+/// signature in front of the swaps.
+fn permit2_calldata(
+    encoded: &EncodedSolution,
+    solution: &Solution,
+    permit: &SignedPermit,
+) -> Vec<u8> {
+    let amount_in = to_u256(&solution.given_amount);
+    let token_in = Address::from_slice(&solution.given_token);
+    let token_out = Address::from_slice(&solution.checked_token);
+    let min_amount_out = to_u256(&solution.checked_amount);
+    let receiver = Address::from_slice(&solution.receiver);
+    let permit_single = permit_single(permit);
+    let signature = alloy::primitives::Bytes::from(permit.signature.clone());
+    let swaps = alloy::primitives::Bytes::from(encoded.swaps.clone());
+
+    let params = if encoded.function_signature.starts_with("splitSwap") {
+        let n_tokens = U256::from(encoded.n_tokens);
+        (
+            amount_in,
+            token_in,
+            token_out,
+            min_amount_out,
+            false,
+            false,
+            n_tokens,
+            receiver,
+            permit_single,
+            signature,
+            swaps,
+        )
+            .abi_encode_params()
+    } else {
+        (
+            amount_in,
+            token_in,
+            token_out,
+            min_amount_out,
+            false,
+            false,
+            receiver,
+            permit_single,
+            signature,
+            swaps,
+        )
+            .abi_encode_params()
+    };
+
+    let selector = keccak256(encoded.function_signature.as_bytes());
+    [&selector[..4], &params[..]].concat()
+}
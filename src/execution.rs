@@ -0,0 +1,212 @@
+use crate::approvals::ApprovalManager;
+use crate::encoder::SwapEncoder;
+use crate::flash_loan::encode_flash_plan;
+use crate::latency::observe_stage;
+use crate::simulator::ForkSimulator;
+use crate::tx_manager::TxManager;
+use crate::types::{
+    EncodedLeg, FlashExecutionPlan, LegFill, Network, Opportunity, OpportunityLeg, SignedPermit,
+    TransferMode, TxEvent,
+};
+use alloy::primitives::{Address, TxHash};
+use futures::future::join_all;
+use num_bigint::BigUint;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tycho_common::models::Chain;
+
+/// Encodes, simulates and sends the legs of accepted opportunities.
+pub struct LiveExecutor {
+    encoder: SwapEncoder,
+    simulator: ForkSimulator,
+    approvals: Arc<ApprovalManager>,
+    tx_managers: HashMap<Chain, Arc<TxManager>>,
+    flash_executors: HashMap<Chain, String>,
+}
+
+impl LiveExecutor {
+    pub fn new(
+        networks: &[Network],
+        private_key: &str,
+        approvals: Arc<ApprovalManager>,
+        tx_managers: HashMap<Chain, Arc<TxManager>>,
+    ) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            encoder: SwapEncoder::new(networks, private_key)?,
+            simulator: ForkSimulator::new(networks, private_key)?,
+            approvals,
+            tx_managers,
+            flash_executors: networks
+                .iter()
+                .filter_map(|n| Some((n.chain, n.flash_executor.clone()?)))
+                .collect(),
+        })
+    }
+
+    /// Simulates every leg before sending any, so one failing simulation rejects the whole
+    /// opportunity. Legs are then sent concurrently, one fill per leg.
+    pub async fn execute(&self, opportunity: &Opportunity) -> Result<Vec<LegFill>, String> {
+        let mut encoded_legs = vec![];
+
+        for leg in &opportunity.legs {
+            let permit = self.permit(leg)?;
+            let encoded = self
+                .encoder
+                .encode_leg(leg, leg.min_amount_out.clone(), permit.as_ref())
+                .map_err(|e| format!("encoding the {:?} leg failed: {}", leg.chain, e))?;
+
+            let simulation = self
+                .simulator
+                .simulate_leg(leg, &encoded)
+                .await
+                .map_err(|e| format!("simulating the {:?} leg failed: {}", leg.chain, e))?;
+            if !simulation.accepted {
+                return Err(format!(
+                    "simulated {:?} leg returns {}, below {}",
+                    leg.chain, simulation.simulated_amount_out, simulation.min_amount_out
+                ));
+            }
+
+            encoded_legs.push((encoded, permit, simulation.gas_used));
+        }
+        observe_stage(&opportunity.timing, "simulation");
+
+        let sends = opportunity
+            .legs
+            .iter()
+            .enumerate()
+            .zip(encoded_legs)
+            .map(|((index, leg), (encoded, permit, gas_used))| async move {
+                let mut fill =
+                    self.send_leg(&opportunity.id, index, leg, encoded, permit, true).await;
+                fill.simulated_gas = Some(gas_used);
+                fill
+            });
+
+        Ok(join_all(sends).await)
+    }
+
+    /// Runs a flash-funded opportunity as one call to the chain's flash executor. The executor
+    /// reverts unless the loan is repaid with `min_profit` left, so a successful replay is the
+    /// whole simulation check.
+    pub async fn execute_flash(
+        &self,
+        opportunity: &Opportunity,
+        plan: &FlashExecutionPlan,
+    ) -> Result<Vec<LegFill>, String> {
+        let chain = plan.leg.chain;
+        let executor = self
+            .flash_executors
+            .get(&chain)
+            .ok_or_else(|| format!("no flash executor on {:?}", chain))?;
+
+        let encoded = encode_flash_plan(&self.encoder, plan, executor)
+            .map_err(|e| format!("encoding the flash loan on {:?} failed: {}", chain, e))?;
+        let gas_used = self
+            .simulator
+            .simulate_call(&encoded)
+            .await
+            .map_err(|e| format!("simulated flash loan on {:?} reverts: {}", chain, e))?;
+        observe_stage(&opportunity.timing, "simulation");
+
+        // the executor receives the output, alongside the loan it repays
+        let mut fill = self.send_leg(&opportunity.id, 0, &plan.leg, encoded, None, false).await;
+        fill.simulated_gas = Some(gas_used);
+
+        Ok(vec![fill])
+    }
+
+    /// The cached permit of the leg's input on Permit2 chains. Legs whose approval or permit
+    /// is not in place yet are rejected rather than waiting on `run_approvals`.
+    fn permit(&self, leg: &OpportunityLeg) -> Result<Option<SignedPermit>, String> {
+        let token = &leg.hops.first().ok_or("leg without hops")?.from_token.address;
+
+        match self.encoder.transfer_mode(leg.chain) {
+            Some(TransferMode::Permit2) => match self.approvals.permit(leg.chain, token) {
+                Some(permit) => Ok(Some(permit)),
+                None => Err(format!("no permit for {} on {:?} yet", token, leg.chain)),
+            },
+            Some(TransferMode::Approval) if !self.approvals.is_approved(leg.chain, token) => {
+                Err(format!("router not approved for {} on {:?} yet", token, leg.chain))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Sends one leg and waits for the outcome, only an included transaction counts as filled.
+    /// When the signer receives the output, the realized amount is read from the receipt's
+    /// transfers, otherwise it is left unknown. An included leg spends the nonce of the permit
+    /// it carried.
+    async fn send_leg(
+        &self,
+        id: &str,
+        leg_index: usize,
+        leg: &OpportunityLeg,
+        encoded: EncodedLeg,
+        permit: Option<SignedPermit>,
+        to_signer: bool,
+    ) -> LegFill {
+        let mut amount_out = None;
+        let filled = match self.tx_managers.get(&leg.chain) {
+            Some(tx_manager) => match tx_manager.execute(id, &encoded).await {
+                Ok(TxEvent::Included { hash, .. }) => {
+                    if let Some(permit) = &permit {
+                        self.approvals.permit_used(leg.chain, permit).await;
+                    }
+                    if to_signer {
+                        amount_out = realized_amount_out(tx_manager, hash, leg).await;
+                    }
+                    true
+                }
+                Ok(event) => {
+                    tracing::warn!(opportunity_id = %id, "Leg not filled: {:?}", event);
+                    false
+                }
+                Err(e) => {
+                    tracing::error!(
+                        opportunity_id = %id,
+                        "Failed to send leg on {:?}: {}",
+                        leg.chain,
+                        e
+                    );
+                    false
+                }
+            },
+            None => {
+                tracing::error!(opportunity_id = %id, "No tx manager for {:?}", leg.chain);
+                false
+            }
+        };
+
+        LegFill {
+            opportunity_id: id.to_string(),
+            leg_index,
+            leg: leg.clone(),
+            filled,
+            flash: !to_signer,
+            amount_out,
+            simulated_gas: None,
+        }
+    }
+}
+
+/// Output token of the leg the signer received in transaction `hash`, `None` when the
+/// receipt cannot be read or the output is the native token, which leaves no transfer log.
+async fn realized_amount_out(
+    tx_manager: &TxManager,
+    hash: TxHash,
+    leg: &OpportunityLeg,
+) -> Option<BigUint> {
+    let token = Address::from_slice(&leg.hops.last()?.to_token.address);
+    if token == Address::ZERO {
+        return None;
+    }
+
+    match tx_manager.received(hash, token).await {
+        Ok(amount) => Some(BigUint::from_bytes_be(&amount.to_be_bytes::<32>())),
+        Err(e) => {
+            tracing::warn!("Realized output of {} unknown: {}", hash, e);
+            None
+        }
+    }
+}
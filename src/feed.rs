@@ -0,0 +1,133 @@
+use crate::latency::observe_stage;
+use crate::metrics::chain_label;
+use crate::server::ServerState;
+use crate::types::{FeedEvent, Opportunity, OpportunityView, PriceEdge, PriceEdgeView};
+use axum::extract::State;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::response::Response;
+use serde::Deserialize;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Fan-out of opportunities and price changes to WebSocket clients. Cloning shares the channel.
+#[derive(Debug, Clone)]
+pub struct Feed {
+    sender: broadcast::Sender<FeedEvent>,
+}
+
+impl Feed {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<FeedEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn publish_opportunity(&self, opportunity: &Opportunity) {
+        observe_stage(&opportunity.timing, "opportunity_event");
+        if self.sender.receiver_count() > 0 {
+            let _ = self.sender.send(FeedEvent::Opportunity(OpportunityView::from(opportunity)));
+        }
+    }
+
+    pub fn publish_price(&self, edge: &PriceEdge) {
+        // skip building the view while nobody listens, this runs for every edge update
+        if self.sender.receiver_count() > 0 {
+            let _ = self.sender.send(FeedEvent::PriceChange(PriceEdgeView::from(edge)));
+        }
+    }
+}
+
+/// Filter sent by clients as a JSON text message, each message replaces the previous one.
+/// Empty `chains` or `symbols` match everything.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Subscription {
+    pub opportunities: bool,
+    pub prices: bool,
+    pub chains: Vec<String>,
+    pub symbols: Vec<String>,
+}
+
+impl Default for Subscription {
+    fn default() -> Self {
+        Self {
+            opportunities: true,
+            prices: false,
+            chains: vec![],
+            symbols: vec![],
+        }
+    }
+}
+
+impl Subscription {
+    fn matches(&self, event: &FeedEvent) -> bool {
+        let kind = match event {
+            FeedEvent::Opportunity(_) => self.opportunities,
+            FeedEvent::PriceChange(_) => self.prices,
+        };
+
+        let chain = self.chains.is_empty()
+            || event.chains().into_iter().any(|chain| {
+                let label = chain_label(chain);
+                self.chains.iter().any(|c| c.eq_ignore_ascii_case(&label))
+            });
+
+        let symbol = self.symbols.is_empty()
+            || event
+                .symbols()
+                .into_iter()
+                .any(|symbol| self.symbols.iter().any(|s| s.eq_ignore_ascii_case(symbol)));
+
+        kind && chain && symbol
+    }
+}
+
+pub async fn ws_handler(ws: WebSocketUpgrade, State(state): State<ServerState>) -> Response {
+    let events = state.feed.subscribe();
+    ws.on_upgrade(move |socket| handle_socket(socket, events))
+}
+
+async fn handle_socket(mut socket: WebSocket, mut events: broadcast::Receiver<FeedEvent>) {
+    let mut subscription = Subscription::default();
+
+    loop {
+        tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    match serde_json::from_str::<Subscription>(text.as_str()) {
+                        Ok(new_subscription) => subscription = new_subscription,
+                        Err(e) => {
+                            let error = serde_json::json!({ "error": e.to_string() }).to_string();
+                            if socket.send(Message::Text(error.into())).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if !subscription.matches(&event) {
+                        continue;
+                    }
+
+                    let Ok(json) = serde_json::to_string(&event) else {
+                        continue;
+                    };
+                    if socket.send(Message::Text(json.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Feed client lagging, skipped {} events", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::encoder::SwapEncoder;
+use crate::metrics::opportunity_rejected;
+use crate::types::{
+    ArbitrageGraph, EncodedLeg, FlashExecutionPlan, FlashLoanProvider, FlashLoanSource, Network,
+    Opportunity, Protocol, SwapHop,
+};
+use crate::utils::abi::IFlashArbExecutor;
+use crate::utils::units::to_u256;
+use alloy::primitives::Address;
+use alloy::sol_types::SolCall;
+use num_bigint::BigUint;
+use std::str::FromStr;
+use tycho_common::Bytes;
+use tycho_simulation::models::Token;
+use tycho_simulation::protocol::models::ProtocolComponent;
+
+const PIPS: u32 = 1_000_000;
+
+/// Every known place to flash-borrow `token` on the network's chain. Balancer V2 and Uniswap V4
+/// lend for free; Uniswap V3 pools charge their swap fee.
+pub fn flash_sources(
+    graph: &ArbitrageGraph,
+    network: &Network,
+    token: &Token,
+) -> Vec<FlashLoanSource> {
+    let mut sources = vec![];
+
+    if let Some(vault) = &network.balancer_vault {
+        sources.push(FlashLoanSource {
+            provider: FlashLoanProvider::BalancerV2,
+            chain: network.chain,
+            lender: Bytes::from_str(vault).unwrap(),
+            token: token.clone(),
+            fee_pips: 0,
+        });
+    }
+
+    sources.push(FlashLoanSource {
+        provider: FlashLoanProvider::UniswapV4,
+        chain: network.chain,
+        lender: Bytes::from_str(&network.pool_manager).unwrap(),
+        token: token.clone(),
+        fee_pips: 0,
+    });
+
+    let v3_pools = graph.graph.edge_weights().filter(|edge| {
+        edge.chain == network.chain
+            && edge.protocol == Protocol::UniswapV3
+            && edge.from_token.address == token.address
+    });
+
+    for edge in v3_pools {
+        let Some(fee) = edge
+            .component
+            .static_attributes
+            .get("fee")
+            .and_then(|fee| u32::try_from(&BigUint::from_bytes_be(fee)).ok())
+        else {
+            continue;
+        };
+
+        sources.push(FlashLoanSource {
+            provider: FlashLoanProvider::UniswapV3,
+            chain: network.chain,
+            lender: edge.component.id.clone(),
+            token: token.clone(),
+            fee_pips: fee,
+        });
+    }
+
+    sources
+}
+
+/// Turns a single-chain cycle into a flash-loan plan: the leg's input is borrowed from the
+/// source the detector priced the cycle with, and the fee is charged against the leg's minimum
+/// output. Slippage bounds must already be applied to the opportunity.
+pub fn plan_flash_execution(
+    opportunity: &Opportunity,
+    source: &FlashLoanSource,
+) -> Result<FlashExecutionPlan, String> {
+    build_flash_plan(opportunity, source).inspect_err(|_| opportunity_rejected("flash_loan"))
+}
+
+fn build_flash_plan(
+    opportunity: &Opportunity,
+    source: &FlashLoanSource,
+) -> Result<FlashExecutionPlan, String> {
+    let [leg] = opportunity.legs.as_slice() else {
+        return Err(format!("{} does not stay on one chain", opportunity.id));
+    };
+    let (Some(first), Some(last)) = (leg.hops.first(), leg.hops.last()) else {
+        return Err(format!("{} has no hops", opportunity.id));
+    };
+
+    if first.from_token.address != last.to_token.address {
+        return Err(format!("{} does not end in the token it starts with", opportunity.id));
+    }
+    if leg.min_amount_out == BigUint::default() {
+        return Err(format!("{} has no minimum output", opportunity.id));
+    }
+
+    if source.chain != leg.chain || source.token.address != first.from_token.address {
+        return Err(format!(
+            "{} is not entered through its flash loan source {}",
+            opportunity.id, source.lender
+        ));
+    }
+    if leg.hops.iter().any(|hop| is_locked_by(source, hop)) {
+        return Err(format!(
+            "{} swaps through its flash loan source {}",
+            opportunity.id, source.lender
+        ));
+    }
+
+    let amount = leg.amount_in();
+    let fee = (&amount * source.fee_pips + (PIPS - 1)) / PIPS;
+    let repay = &amount + &fee;
+
+    if leg.min_amount_out <= repay {
+        return Err(format!(
+            "{} does not cover the flash loan at its minimum output: {} <= {}",
+            opportunity.id, leg.min_amount_out, repay
+        ));
+    }
+
+    Ok(FlashExecutionPlan {
+        opportunity_id: opportunity.id.clone(),
+        min_profit: &leg.min_amount_out - &repay,
+        source: source.clone(),
+        leg: leg.clone(),
+        amount,
+        fee,
+    })
+}
+
+/// Encodes the plan as a single call to the chain's flash executor, which borrows, runs the
+/// router swap, repays and keeps the profit.
+pub fn encode_flash_plan(
+    encoder: &SwapEncoder,
+    plan: &FlashExecutionPlan,
+    executor: &str,
+) -> Result<EncodedLeg, anyhow::Error> {
+    let executor = Bytes::from_str(executor)?;
+    let min_amount_out = plan.leg.min_amount_out.clone();
+    let swap = encoder.encode_leg_for(&plan.leg, min_amount_out, executor.clone())?;
+
+    let data = IFlashArbExecutor::executeCall {
+        provider: plan.source.provider as u8,
+        lender: Address::from_slice(&plan.source.lender),
+        token: Address::from_slice(&plan.source.token.address),
+        amount: to_u256(&plan.amount),
+        router: Address::from_slice(&swap.to),
+        swapData: swap.data.into(),
+        minProfit: to_u256(&plan.min_profit),
+    }
+    .abi_encode();
+
+    Ok(EncodedLeg {
+        chain: plan.leg.chain,
+        to: executor,
+        value: BigUint::default(),
+        data,
+    })
+}
+
+fn is_locked_by(source: &FlashLoanSource, hop: &SwapHop) -> bool {
+    locks_pool(source, hop.protocol, &hop.component)
+}
+
+/// Lenders are locked while the loan is out: a V3 pool cannot be swapped through, and the
+/// Balancer vault and V4 pool manager reject any nested swap through their own pools.
+pub fn locks_pool(
+    source: &FlashLoanSource,
+    protocol: Protocol,
+    component: &ProtocolComponent,
+) -> bool {
+    match source.provider {
+        FlashLoanProvider::BalancerV2 => protocol == Protocol::VmBalancerV2,
+        FlashLoanProvider::UniswapV3 => component.id == source.lender,
+        FlashLoanProvider::UniswapV4 => protocol == Protocol::UniswapV4,
+    }
+}
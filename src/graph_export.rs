@@ -0,0 +1,208 @@
+use crate::metrics::chain_label;
+use crate::types::{GraphSnapshot, PegType, TokenNode};
+use petgraph::stable_graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use tycho_common::models::Chain;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphFormat {
+    #[default]
+    Dot,
+    Graphml,
+}
+
+impl GraphFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "text/vnd.graphviz",
+            GraphFormat::Graphml => "application/graphml+xml",
+        }
+    }
+}
+
+/// Restricts an export to one chain and/or the neighbourhood of one asset.
+#[derive(Debug, Default, Clone)]
+pub struct GraphFilter {
+    /// Only edges on this chain, and nodes with a token on it
+    pub chain: Option<Chain>,
+    /// Only edges touching this asset, and their endpoints
+    pub symbol: Option<String>,
+}
+
+/// Serializes the part of the graph selected by `filter`. Nodes without edges are kept unless
+/// filtering by symbol, they are what points to missing pools.
+pub fn dump_graph(graph: &GraphSnapshot, filter: &GraphFilter, format: GraphFormat) -> String {
+    let (nodes, edges) = select(graph, filter);
+
+    match format {
+        GraphFormat::Dot => to_dot(graph, filter, &nodes, &edges),
+        GraphFormat::Graphml => to_graphml(graph, filter, &nodes, &edges),
+    }
+}
+
+fn select(graph: &GraphSnapshot, filter: &GraphFilter) -> (Vec<NodeIndex>, Vec<EdgeIndex>) {
+    let matches_symbol = |idx: NodeIndex| {
+        filter
+            .symbol
+            .as_deref()
+            .is_none_or(|symbol| graph.graph[idx].symbol.eq_ignore_ascii_case(symbol))
+    };
+
+    let edges = graph
+        .graph
+        .edge_references()
+        .filter(|edge| filter.chain.is_none_or(|chain| edge.weight().view.chain == chain))
+        .filter(|edge| matches_symbol(edge.source()) || matches_symbol(edge.target()))
+        .map(|edge| edge.id())
+        .collect::<Vec<_>>();
+
+    let mut nodes = BTreeSet::new();
+    for idx in &edges {
+        let (source, target) = graph.graph.edge_endpoints(*idx).unwrap();
+        nodes.insert(source);
+        nodes.insert(target);
+    }
+    if filter.symbol.is_none() {
+        nodes.extend(graph.graph.node_indices().filter(|idx| {
+            filter.chain.is_none_or(|chain| graph.graph[*idx].tokens.contains_key(&chain))
+        }));
+    }
+
+    (nodes.into_iter().collect(), edges)
+}
+
+/// `chain: address` per token of the node, limited to the filtered chain.
+fn node_tokens(node: &TokenNode, filter: &GraphFilter) -> Vec<String> {
+    let mut tokens = node
+        .tokens
+        .iter()
+        .filter(|(chain, _)| filter.chain.is_none_or(|c| c == **chain))
+        .flat_map(|(chain, tokens)| {
+            tokens
+                .iter()
+                .map(move |token| format!("{}: {}", chain_label(*chain), token.address))
+        })
+        .collect::<Vec<_>>();
+    tokens.sort();
+
+    tokens
+}
+
+fn peg_label(peg: PegType) -> &'static str {
+    match peg {
+        PegType::Canonical => "canonical",
+        PegType::Soft => "soft",
+    }
+}
+
+fn to_dot(
+    graph: &GraphSnapshot,
+    filter: &GraphFilter,
+    nodes: &[NodeIndex],
+    edges: &[EdgeIndex],
+) -> String {
+    let mut dot = String::from("digraph arbitrage {\n");
+
+    for idx in nodes {
+        let node = &graph.graph[*idx];
+        let mut label = vec![format!("{} ({})", node.symbol, peg_label(node.peg))];
+        label.extend(node_tokens(node, filter));
+
+        let _ = writeln!(
+            dot,
+            "  n{} [label=\"{}\"];",
+            idx.index(),
+            label.iter().map(|line| dot_escape(line)).collect::<Vec<_>>().join("\\n")
+        );
+    }
+
+    for idx in edges {
+        let (source, target) = graph.graph.edge_endpoints(*idx).unwrap();
+        let edge = &graph.graph[*idx].view;
+
+        let _ = writeln!(
+            dot,
+            "  n{} -> n{} [label=\"{} {}\\n{:.6}\", pool=\"{}\"];",
+            source.index(),
+            target.index(),
+            chain_label(edge.chain),
+            edge.protocol,
+            edge.price,
+            dot_escape(&edge.pool_address)
+        );
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn to_graphml(
+    graph: &GraphSnapshot,
+    filter: &GraphFilter,
+    nodes: &[NodeIndex],
+    edges: &[EdgeIndex],
+) -> String {
+    let mut xml = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"symbol\" for=\"node\" attr.name=\"symbol\" attr.type=\"string\"/>\n",
+        "  <key id=\"peg\" for=\"node\" attr.name=\"peg\" attr.type=\"string\"/>\n",
+        "  <key id=\"tokens\" for=\"node\" attr.name=\"tokens\" attr.type=\"string\"/>\n",
+        "  <key id=\"chain\" for=\"edge\" attr.name=\"chain\" attr.type=\"string\"/>\n",
+        "  <key id=\"protocol\" for=\"edge\" attr.name=\"protocol\" attr.type=\"string\"/>\n",
+        "  <key id=\"pool\" for=\"edge\" attr.name=\"pool\" attr.type=\"string\"/>\n",
+        "  <key id=\"from_token\" for=\"edge\" attr.name=\"from_token\" attr.type=\"string\"/>\n",
+        "  <key id=\"to_token\" for=\"edge\" attr.name=\"to_token\" attr.type=\"string\"/>\n",
+        "  <key id=\"price\" for=\"edge\" attr.name=\"price\" attr.type=\"double\"/>\n",
+        "  <graph id=\"arbitrage\" edgedefault=\"directed\">\n",
+    ));
+
+    for idx in nodes {
+        let node = &graph.graph[*idx];
+        let _ = writeln!(xml, "    <node id=\"n{}\">", idx.index());
+        graphml_data(&mut xml, "symbol", &node.symbol);
+        graphml_data(&mut xml, "peg", peg_label(node.peg));
+        graphml_data(&mut xml, "tokens", &node_tokens(node, filter).join("; "));
+        xml.push_str("    </node>\n");
+    }
+
+    for idx in edges {
+        let (source, target) = graph.graph.edge_endpoints(*idx).unwrap();
+        let edge = &graph.graph[*idx].view;
+
+        let _ = writeln!(
+            xml,
+            "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\">",
+            idx.index(),
+            source.index(),
+            target.index()
+        );
+        graphml_data(&mut xml, "chain", &chain_label(edge.chain));
+        graphml_data(&mut xml, "protocol", &edge.protocol);
+        graphml_data(&mut xml, "pool", &edge.pool_address);
+        graphml_data(&mut xml, "from_token", &edge.from_token.address.to_string());
+        graphml_data(&mut xml, "to_token", &edge.to_token.address.to_string());
+        graphml_data(&mut xml, "price", &edge.price.to_string());
+        xml.push_str("    </edge>\n");
+    }
+
+    xml.push_str("  </graph>\n</graphml>\n");
+    xml
+}
+
+fn graphml_data(xml: &mut String, key: &str, value: &str) {
+    let value = value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;");
+    let _ = writeln!(xml, "      <data key=\"{}\">{}</data>", key, value);
+}
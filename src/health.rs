@@ -0,0 +1,63 @@
+use crate::server::ServerState;
+use crate::types::{ChainHealthView, ChainStatus, GraphSnapshot, HealthView, Network};
+use crate::utils::constants::{CHAIN_STALE_AFTER_BLOCKS, CHAIN_STALE_MIN_SECS};
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use std::time::Duration;
+
+/// Whether the chain has gone silent for more than its stale-after block count, and at least
+/// `CHAIN_STALE_MIN_SECS` so that a short gap on a fast chain does not flap the health checks.
+pub fn is_stale(status: &ChainStatus, network: &Network) -> bool {
+    let blocks = network.stale_after_blocks.unwrap_or(CHAIN_STALE_AFTER_BLOCKS);
+    let stale_after = (Duration::from_millis(network.block_time_ms) * blocks)
+        .max(Duration::from_secs(CHAIN_STALE_MIN_SECS));
+
+    status.updated_at.elapsed() > stale_after
+}
+
+fn health(graph: &GraphSnapshot, networks: &[Network]) -> HealthView {
+    let chains = networks
+        .iter()
+        .map(|network| {
+            let status = graph.chain_status.get(&network.chain);
+
+            ChainHealthView {
+                chain: network.name.clone(),
+                block: status.map(|status| status.timing.block),
+                last_update_secs: status.map(|status| status.updated_at.elapsed().as_secs_f64()),
+                stale: status.is_some_and(|status| is_stale(status, network)),
+            }
+        })
+        .collect();
+
+    HealthView {
+        initialized: graph.initialized,
+        chains,
+    }
+}
+
+/// Liveness: fails once any chain that delivered a snapshot has gone silent. Chains still
+/// waiting for their first snapshot only hold back readiness.
+pub async fn healthz(State(state): State<ServerState>) -> (StatusCode, Json<HealthView>) {
+    let health = health(&state.graph.borrow(), &state.networks);
+
+    let status = if health.chains.iter().any(|chain| chain.stale) {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+
+    (status, Json(health))
+}
+
+/// Readiness: tokens are in the graph and every chain has a fresh snapshot.
+pub async fn readyz(State(state): State<ServerState>) -> (StatusCode, Json<HealthView>) {
+    let health = health(&state.graph.borrow(), &state.networks);
+
+    let ready = health.initialized
+        && health.chains.iter().all(|chain| chain.block.is_some() && !chain.stale);
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    (status, Json(health))
+}
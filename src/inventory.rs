@@ -0,0 +1,196 @@
+use crate::alerts::alert;
+use crate::metrics::opportunity_rejected;
+use crate::types::{AlertEvent, LegFill, Network, Opportunity, TokenConfig};
+use crate::utils::abi::IERC20;
+use crate::utils::rpc::create_provider;
+use crate::utils::units::from_units;
+use alloy::primitives::Address;
+use num_bigint::BigUint;
+use std::collections::HashMap;
+use tycho_common::Bytes;
+use tycho_common::models::Chain;
+use tycho_simulation::models::Token;
+
+type TokenKey = (Chain, Bytes);
+
+/// Token balances held on each chain, minus what is reserved by accepted opportunities.
+pub struct InventoryManager {
+    balances: HashMap<TokenKey, BigUint>,
+    reserved: HashMap<TokenKey, BigUint>,
+    reservations: HashMap<String, Vec<(TokenKey, BigUint)>>,
+}
+
+impl InventoryManager {
+    pub fn new() -> Self {
+        Self {
+            balances: HashMap::new(),
+            reserved: HashMap::new(),
+            reservations: HashMap::new(),
+        }
+    }
+
+    /// Replaces the known balances of `chain`, reservations are kept.
+    pub fn set_chain_balances(&mut self, chain: Chain, balances: HashMap<Bytes, BigUint>) {
+        self.balances.retain(|(c, _), _| *c != chain);
+
+        for (address, balance) in balances {
+            self.balances.insert((chain, address), balance);
+        }
+    }
+
+    pub fn balance(&self, chain: Chain, token: &Bytes) -> BigUint {
+        self.balances.get(&(chain, token.clone())).cloned().unwrap_or_default()
+    }
+
+    pub fn available(&self, chain: Chain, token: &Bytes) -> BigUint {
+        let balance = self.balance(chain, token);
+        let reserved = self.reserved.get(&(chain, token.clone())).cloned().unwrap_or_default();
+
+        if balance > reserved { balance - reserved } else { BigUint::default() }
+    }
+
+    /// Largest input the sizing step may use for a leg starting with `token` on `chain`.
+    pub fn cap_amount_in(&self, chain: Chain, token: &Bytes, desired: BigUint) -> BigUint {
+        desired.min(self.available(chain, token))
+    }
+
+    /// Reserves the input of every leg, all or nothing.
+    pub fn reserve(&mut self, opportunity: &Opportunity) -> Result<(), String> {
+        let mut required: HashMap<TokenKey, BigUint> = HashMap::new();
+
+        for leg in &opportunity.legs {
+            let Some(first) = leg.hops.first() else {
+                continue;
+            };
+            *required.entry((leg.chain, first.from_token.address.clone())).or_default() +=
+                leg.amount_in();
+        }
+
+        for ((chain, token), amount) in &required {
+            let available = self.available(*chain, token);
+            if &available < amount {
+                opportunity_rejected("inventory");
+                let message = format!(
+                    "Insufficient inventory of {} on {:?}: need {}, available {}",
+                    token, chain, amount, available
+                );
+                alert(AlertEvent::RiskLimit {
+                    limit: "inventory".into(),
+                    subject: format!("{:?}:{}", chain, token),
+                    message: message.clone(),
+                });
+                return Err(message);
+            }
+        }
+
+        for (key, amount) in &required {
+            *self.reserved.entry(key.clone()).or_default() += amount;
+        }
+        self.reservations.insert(opportunity.id.clone(), required.into_iter().collect());
+
+        Ok(())
+    }
+
+    /// Drops whatever is still reserved for an opportunity, e.g. when it is rejected.
+    pub fn release(&mut self, opportunity_id: &str) {
+        let Some(reservations) = self.reservations.remove(opportunity_id) else {
+            return;
+        };
+
+        for (key, amount) in reservations {
+            self.unreserve(&key, &amount);
+        }
+    }
+
+    /// Applies a fill: the leg's reserved input is spent and its output credited. An unknown
+    /// output is credited at the leg's minimum, which the router enforces. Flash-funded legs
+    /// neither reserve nor receive anything, the executor contract keeps their profit.
+    pub fn settle(&mut self, fill: &LegFill) {
+        let (Some(first), Some(last)) = (fill.leg.hops.first(), fill.leg.hops.last()) else {
+            return;
+        };
+        if fill.flash {
+            return;
+        }
+
+        let spent_key = (fill.leg.chain, first.from_token.address.clone());
+        let amount_in = fill.leg.amount_in();
+
+        self.unreserve(&spent_key, &amount_in);
+        if let Some(reservations) = self.reservations.get_mut(&fill.opportunity_id) {
+            reservations.retain(|(key, _)| key != &spent_key);
+            if reservations.is_empty() {
+                self.reservations.remove(&fill.opportunity_id);
+            }
+        }
+
+        if !fill.filled {
+            return;
+        }
+        let amount_out = fill.amount_out.as_ref().unwrap_or(&fill.leg.min_amount_out);
+
+        let received_key = (fill.leg.chain, last.to_token.address.clone());
+        *self.balances.entry(received_key).or_default() += amount_out;
+
+        let balance = self.balances.entry(spent_key).or_default();
+        *balance = if *balance > amount_in { &*balance - &amount_in } else { BigUint::default() };
+    }
+
+    fn unreserve(&mut self, key: &TokenKey, amount: &BigUint) {
+        if let Some(reserved) = self.reserved.get_mut(key) {
+            *reserved = if *reserved > *amount { &*reserved - amount } else { BigUint::default() };
+        }
+    }
+}
+
+/// Balances of `owner` for the chain's tokens: configured inventory when present, otherwise
+/// the on-chain ERC-20 balance. A token whose balance can't be fetched is left out, so it has
+/// no inventory, instead of failing the whole chain.
+pub async fn fetch_chain_balances(
+    network: &Network,
+    config_tokens: &[TokenConfig],
+    tokens: &HashMap<Bytes, Token>,
+    owner: Option<Address>,
+) -> Result<HashMap<Bytes, BigUint>, anyhow::Error> {
+    let provider = create_provider(network)?;
+    let mut balances = HashMap::new();
+
+    for (address, token) in tokens {
+        let configured = config_tokens
+            .iter()
+            .find(|t| t.address.eq_ignore_ascii_case(&address.to_string()))
+            .and_then(|t| t.inventory);
+
+        let balance = match (configured, owner) {
+            (Some(amount), _) => from_units(amount, token.decimals),
+            (None, Some(owner)) => {
+                let contract = IERC20::new(Address::from_slice(address), &provider);
+                match contract.balanceOf(owner).call().await {
+                    Ok(balance) => BigUint::from_bytes_be(&balance.to_be_bytes::<32>()),
+                    Err(e) => {
+                        tracing::warn!(
+                            "Skipping inventory of {} on {}: {}",
+                            token.symbol,
+                            network.name,
+                            e
+                        );
+                        continue;
+                    }
+                }
+            }
+            (None, None) => {
+                tracing::warn!(
+                    "Skipping inventory of {} on {}: not configured and no signer to look it up",
+                    token.symbol,
+                    network.name
+                );
+                continue;
+            }
+        };
+
+        tracing::info!("Inventory {} on {}: {}", token.symbol, network.name, balance);
+        balances.insert(address.clone(), balance);
+    }
+
+    Ok(balances)
+}
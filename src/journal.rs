@@ -0,0 +1,306 @@
+use crate::types::{Decision, LegFill, Opportunity, TxEvent};
+use rusqlite::{Connection, params};
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+
+/// Schema migrations, applied in order. The index of the last applied one is stored in
+/// SQLite's `user_version`, so entries must never be edited or reordered once released.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE opportunities (
+        id TEXT PRIMARY KEY,
+        detected_at INTEGER NOT NULL DEFAULT (unixepoch()),
+        decision TEXT,
+        reason TEXT,
+        decided_at INTEGER
+    );
+    CREATE TABLE legs (
+        opportunity_id TEXT NOT NULL REFERENCES opportunities(id),
+        leg_index INTEGER NOT NULL,
+        chain TEXT NOT NULL,
+        token_in TEXT NOT NULL,
+        token_out TEXT NOT NULL,
+        amount_in TEXT NOT NULL,
+        expected_amount_out TEXT NOT NULL,
+        min_amount_out TEXT NOT NULL,
+        tolerance_bps INTEGER NOT NULL,
+        realized_amount_out TEXT,
+        PRIMARY KEY (opportunity_id, leg_index)
+    );
+    CREATE TABLE transactions (
+        hash TEXT PRIMARY KEY,
+        tx_id TEXT NOT NULL,
+        chain TEXT NOT NULL,
+        nonce INTEGER,
+        status TEXT NOT NULL,
+        block INTEGER,
+        gas_used INTEGER,
+        updated_at INTEGER NOT NULL DEFAULT (unixepoch())
+    );
+    CREATE INDEX opportunities_detected_at ON opportunities(detected_at);
+    CREATE INDEX transactions_tx_id ON transactions(tx_id);",
+    "ALTER TABLE legs ADD COLUMN simulated_gas INTEGER;",
+];
+
+enum JournalEntry {
+    Opportunity(Opportunity),
+    Decision(String, Decision),
+    Fill(LegFill),
+    TxEvent(TxEvent),
+}
+
+/// Embedded SQLite record of opportunities, the decisions taken on them and the transactions
+/// sent for them. Amounts are stored as decimal strings of raw token amounts. Entries are
+/// queued to a writer thread, so recording never blocks the caller on disk IO; failed writes
+/// are logged by the writer.
+pub struct Journal {
+    sender: mpsc::UnboundedSender<JournalEntry>,
+}
+
+impl Journal {
+    pub fn open(path: &str) -> Result<Self, anyhow::Error> {
+        let mut connection = Connection::open(path)?;
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        migrate(&mut connection)?;
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        std::thread::spawn(move || JournalWriter { connection }.run(receiver));
+
+        Ok(Self { sender })
+    }
+
+    fn send(&self, entry: JournalEntry) {
+        if self.sender.send(entry).is_err() {
+            tracing::error!("Journal writer has stopped");
+        }
+    }
+
+    pub fn record_opportunity(&self, opportunity: &Opportunity) {
+        self.send(JournalEntry::Opportunity(opportunity.clone()));
+    }
+
+    pub fn record_decision(&self, opportunity_id: &str, decision: &Decision) {
+        self.send(JournalEntry::Decision(opportunity_id.to_string(), decision.clone()));
+    }
+
+    /// Stores the realized output of a leg next to its expected output, with the gas its
+    /// simulation estimated.
+    pub fn record_fill(&self, fill: &LegFill) {
+        self.send(JournalEntry::Fill(fill.clone()));
+    }
+
+    pub fn record_tx_event(&self, event: &TxEvent) {
+        self.send(JournalEntry::TxEvent(event.clone()));
+    }
+}
+
+struct JournalWriter {
+    connection: Connection,
+}
+
+impl JournalWriter {
+    fn run(mut self, mut receiver: mpsc::UnboundedReceiver<JournalEntry>) {
+        while let Some(entry) = receiver.blocking_recv() {
+            let result = match &entry {
+                JournalEntry::Opportunity(opportunity) => self.write_opportunity(opportunity),
+                JournalEntry::Decision(id, decision) => self.write_decision(id, decision),
+                JournalEntry::Fill(fill) => self.write_fill(fill),
+                JournalEntry::TxEvent(event) => self.write_tx_event(event),
+            };
+
+            if let Err(e) = result {
+                tracing::error!("Failed to journal {}: {}", entry.describe(), e);
+            }
+        }
+    }
+
+    fn write_opportunity(&mut self, opportunity: &Opportunity) -> Result<(), anyhow::Error> {
+        let tx = self.connection.transaction()?;
+
+        tx.execute(
+            "INSERT OR IGNORE INTO opportunities (id) VALUES (?1)",
+            params![opportunity.id],
+        )?;
+
+        for (index, leg) in opportunity.legs.iter().enumerate() {
+            let (Some(first), Some(last)) = (leg.hops.first(), leg.hops.last()) else {
+                continue;
+            };
+
+            tx.execute(
+                "INSERT OR REPLACE INTO legs (opportunity_id, leg_index, chain, token_in,
+                    token_out, amount_in, expected_amount_out, min_amount_out, tolerance_bps)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    opportunity.id,
+                    index as i64,
+                    format!("{:?}", leg.chain),
+                    first.from_token.address.to_string(),
+                    last.to_token.address.to_string(),
+                    leg.amount_in().to_string(),
+                    leg.amount_out().to_string(),
+                    leg.min_amount_out.to_string(),
+                    leg.tolerance_bps as i64,
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn write_decision(
+        &self,
+        opportunity_id: &str,
+        decision: &Decision,
+    ) -> Result<(), anyhow::Error> {
+        let reason = match decision {
+            Decision::Rejected { reason } => Some(reason.as_str()),
+            _ => None,
+        };
+
+        self.connection.execute(
+            "INSERT INTO opportunities (id, decision, reason, decided_at)
+             VALUES (?1, ?2, ?3, unixepoch())
+             ON CONFLICT (id) DO UPDATE SET
+                decision = excluded.decision,
+                reason = excluded.reason,
+                decided_at = excluded.decided_at",
+            params![opportunity_id, decision.to_str(), reason],
+        )?;
+
+        Ok(())
+    }
+
+    fn write_fill(&self, fill: &LegFill) -> Result<(), anyhow::Error> {
+        self.connection.execute(
+            "UPDATE legs SET realized_amount_out = ?1, simulated_gas = ?4
+             WHERE opportunity_id = ?2 AND leg_index = ?3",
+            params![
+                fill.amount_out.as_ref().map(|amount| amount.to_string()),
+                fill.opportunity_id,
+                fill.leg_index as i64,
+                fill.simulated_gas.map(|gas| gas as i64),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    fn write_tx_event(&self, event: &TxEvent) -> Result<(), anyhow::Error> {
+        let connection = &self.connection;
+
+        match event {
+            TxEvent::Submitted {
+                chain,
+                id,
+                hash,
+                nonce,
+            } => {
+                connection.execute(
+                    "INSERT OR REPLACE INTO transactions (hash, tx_id, chain, nonce, status)
+                     VALUES (?1, ?2, ?3, ?4, 'submitted')",
+                    params![hash.to_string(), id, format!("{:?}", chain), *nonce as i64],
+                )?;
+            }
+            TxEvent::Replaced {
+                chain,
+                id,
+                old_hash,
+                hash,
+            } => {
+                connection.execute(
+                    "UPDATE transactions SET status = 'replaced', updated_at = unixepoch()
+                     WHERE hash = ?1",
+                    params![old_hash.to_string()],
+                )?;
+                connection.execute(
+                    "INSERT OR REPLACE INTO transactions (hash, tx_id, chain, nonce, status)
+                     SELECT ?1, ?2, ?3, nonce, 'submitted' FROM transactions WHERE hash = ?4",
+                    params![hash.to_string(), id, format!("{:?}", chain), old_hash.to_string()],
+                )?;
+            }
+            // the opportunity's decision is recorded by the executor once every leg resolved
+            TxEvent::Included {
+                hash,
+                block,
+                gas_used,
+                ..
+            } => {
+                update_receipt(connection, hash.to_string(), "included", *block, *gas_used)?;
+            }
+            TxEvent::Reverted {
+                hash,
+                block,
+                gas_used,
+                ..
+            } => {
+                update_receipt(connection, hash.to_string(), "reverted", *block, *gas_used)?;
+            }
+            TxEvent::Dropped { chain, id, nonce } => {
+                connection.execute(
+                    "UPDATE transactions SET status = 'dropped', updated_at = unixepoch()
+                     WHERE tx_id = ?1 AND chain = ?2 AND nonce = ?3 AND status = 'submitted'",
+                    params![id, format!("{:?}", chain), *nonce as i64],
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl JournalEntry {
+    fn describe(&self) -> String {
+        match self {
+            JournalEntry::Opportunity(opportunity) => format!("opportunity {}", opportunity.id),
+            JournalEntry::Decision(id, _) => format!("decision on {}", id),
+            JournalEntry::Fill(fill) => format!("fill of {}", fill.opportunity_id),
+            JournalEntry::TxEvent(event) => format!("{:?}", event),
+        }
+    }
+}
+
+fn update_receipt(
+    connection: &Connection,
+    hash: String,
+    status: &str,
+    block: u64,
+    gas_used: u64,
+) -> Result<(), anyhow::Error> {
+    connection.execute(
+        "UPDATE transactions SET status = ?2, block = ?3, gas_used = ?4, updated_at = unixepoch()
+         WHERE hash = ?1",
+        params![hash, status, block as i64, gas_used as i64],
+    )?;
+
+    Ok(())
+}
+
+fn migrate(connection: &mut Connection) -> Result<(), anyhow::Error> {
+    let version: usize =
+        connection.pragma_query_value(None, "user_version", |row| row.get::<_, i64>(0))? as usize;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        tracing::info!("Applying journal migration {}", index + 1);
+
+        let tx = connection.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", (index + 1) as i64)?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+/// Journals every transaction event of one chain's tx manager.
+pub async fn run_tx_journal(journal: Arc<Journal>, mut events: broadcast::Receiver<TxEvent>) {
+    loop {
+        match events.recv().await {
+            Ok(event) => journal.record_tx_event(&event),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!("Journal missed {} transaction events", skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::metrics::{BLOCK_LAG_SECONDS, STAGE_LATENCY_SECONDS, chain_label};
+use crate::types::{BlockTiming, Network};
+use crate::utils::constants::BLOCK_LAG_ANCHOR_SECS;
+use alloy::eips::BlockNumberOrTag;
+use alloy::providers::{DynProvider, Provider};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, UNIX_EPOCH};
+
+/// Records the time since `timing`'s block update was received, at the end of `stage`.
+pub fn observe_stage(timing: &BlockTiming, stage: &str) {
+    STAGE_LATENCY_SECONDS
+        .with_label_values(&[&chain_label(timing.chain), stage])
+        .observe(timing.received_at.elapsed().as_secs_f64());
+}
+
+/// Block timestamps of a chain, extrapolated at the chain's block time from the last fetched
+/// header, so the lag is measured without a request per block. The header is re-fetched every
+/// `BLOCK_LAG_ANCHOR_SECS`, bounding the drift from missed slots.
+#[derive(Clone)]
+pub struct BlockClock {
+    provider: DynProvider,
+    block_time_ms: u64,
+    anchor: Arc<Mutex<Option<Anchor>>>,
+    fetching: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Anchor {
+    block: u64,
+    timestamp: u64,
+    fetched_at: Instant,
+}
+
+impl BlockClock {
+    pub fn new(provider: DynProvider, network: &Network) -> Self {
+        Self {
+            provider,
+            block_time_ms: network.block_time_ms,
+            anchor: Arc::new(Mutex::new(None)),
+            fetching: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Records how long after its timestamp `timing`'s block arrived. Block timestamps only
+    /// have second precision.
+    pub fn record_block_lag(&self, timing: BlockTiming) {
+        let anchor = *self.anchor.lock().unwrap();
+
+        match anchor {
+            Some(anchor)
+                if timing.block >= anchor.block
+                    && anchor.fetched_at.elapsed().as_secs() < BLOCK_LAG_ANCHOR_SECS =>
+            {
+                let elapsed_ms = (timing.block - anchor.block) * self.block_time_ms;
+                observe_block_lag(&timing, anchor.timestamp as f64 + elapsed_ms as f64 / 1000.0);
+            }
+            _ => {
+                if !self.fetching.swap(true, Ordering::AcqRel) {
+                    tokio::spawn(self.clone().fetch_anchor(timing));
+                }
+            }
+        }
+    }
+
+    /// Fetches the header of `timing`'s block as the new anchor, off the hot path.
+    async fn fetch_anchor(self, timing: BlockTiming) {
+        let number = BlockNumberOrTag::Number(timing.block);
+
+        match self.provider.get_block_by_number(number).await {
+            Ok(Some(block)) => {
+                *self.anchor.lock().unwrap() = Some(Anchor {
+                    block: timing.block,
+                    timestamp: block.header.timestamp,
+                    fetched_at: Instant::now(),
+                });
+                observe_block_lag(&timing, block.header.timestamp as f64);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::debug!(block = timing.block, "Failed to fetch block for lag: {}", e),
+        }
+
+        self.fetching.store(false, Ordering::Release);
+    }
+}
+
+fn observe_block_lag(timing: &BlockTiming, block_timestamp: f64) {
+    let received = timing.received_at_system.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let lag = received.as_secs_f64() - block_timestamp;
+
+    BLOCK_LAG_SECONDS
+        .with_label_values(&[&chain_label(timing.chain)])
+        .observe(lag.max(0.0));
+}
@@ -0,0 +1,54 @@
+use crate::types::{LogFormat, LogRotation, LoggingConfig};
+use crate::utils::constants::LOG_FILE_NAME;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+fn fmt_layer<W>(json: bool, writer: W, ansi: bool) -> BoxedLayer
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer).with_ansi(ansi);
+
+    if json {
+        // event fields at the top level, fields of the enclosing span (chain, block) under "span"
+        layer.json().flatten_event(true).with_current_span(true).with_span_list(false).boxed()
+    } else {
+        layer.boxed()
+    }
+}
+
+/// Installs the global subscriber: stdout unless the dashboard owns the terminal, plus a rolling
+/// file when a log directory is configured. The dashboard falls back to the working directory.
+/// The returned guard flushes the file writer and must live as long as the process.
+pub fn setup_tracing(config: &LoggingConfig, json: bool, dashboard: bool) -> Option<WorkerGuard> {
+    let filter = EnvFilter::from_default_env().add_directive("info".parse().unwrap()); // Default to info level if RUST_LOG is not set
+    let json = json || config.format == LogFormat::Json;
+
+    let mut layers = vec![];
+    if !dashboard {
+        layers.push(fmt_layer(json, std::io::stdout, true));
+    }
+
+    let directory = config.directory.clone().or_else(|| dashboard.then(|| ".".to_string()));
+    let guard = directory.map(|directory| {
+        let appender = match config.rotation {
+            LogRotation::Hourly => rolling::hourly(&directory, LOG_FILE_NAME),
+            LogRotation::Daily => rolling::daily(&directory, LOG_FILE_NAME),
+            LogRotation::Never => rolling::never(&directory, LOG_FILE_NAME),
+        };
+        let (writer, guard) = tracing_appender::non_blocking(appender);
+        layers.push(fmt_layer(json, writer, false));
+
+        guard
+    });
+
+    tracing_subscriber::registry().with(layers).with(filter).init();
+
+    guard
+}
@@ -1,30 +1,101 @@
+mod alerts;
+mod api;
+mod approvals;
 mod arbitrage_graph;
+mod config_watcher;
 mod configuration;
+mod dashboard;
+mod detector;
+mod encoder;
+mod execution;
+mod feed;
+mod flash_loan;
+mod graph_export;
+mod health;
+mod inventory;
+mod journal;
+mod latency;
+mod logging;
+mod metrics;
+mod paper_trader;
+mod rebalancer;
+mod recorder;
+mod server;
+mod simulator;
+mod slippage;
 mod stream_builder;
+mod tx_manager;
 mod tycho_api;
 mod types;
 mod utils;
 
-use configuration::load_config;
-use futures::StreamExt;
+use alerts::{ErrorWindow, alert, start_alerts};
+use alloy::primitives::Address;
+use approvals::{ApprovalManager, run_approvals};
+use config_watcher::watch_config;
+use configuration::{
+    asset_groups, diff_config, format_issues, inventory_targets, load_config, needs_signer,
+    read_config, validate_config,
+};
+use dashboard::run_dashboard;
+use detector::{Cycle, detect_cycles, size_cycle};
+use execution::LiveExecutor;
+use feed::Feed;
+use flash_loan::plan_flash_execution;
+use futures::{Stream, StreamExt};
 use futures::future::select_all;
+use inventory::{InventoryManager, fetch_chain_balances};
+use journal::{Journal, run_tx_journal};
+use latency::{BlockClock, observe_stage};
+use logging::setup_tracing;
+use metrics::{
+    BLOCK_UPDATE_SECONDS, BLOCK_UPDATES, GRAPH_EDGES, GRAPH_NODES, NEW_PAIRS, REMOVED_PAIRS,
+    STREAM_ERRORS, chain_label, opportunity_accepted, timed_lock,
+};
+use paper_trader::PaperTrader;
+use rebalancer::run_rebalancer;
+use recorder::{PriceRecorder, stop_recorder};
+use server::{ServerState, publish_snapshots, run_server};
+use slippage::apply_slippage_bounds;
 use std::collections::HashMap;
+use std::fmt::Debug;
 use std::process;
-use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Instant, SystemTime};
 use stream_builder::create_protocol_stream_builder;
-use tokio::sync::Mutex;
-use tycho_api::get_tokens;
+use tokio::sync::{Mutex, mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+use tx_manager::TxManager;
+use tycho_common::Bytes;
 use tycho_common::models::Chain;
+use tycho_simulation::models::Token;
+use tycho_simulation::protocol::models::BlockUpdate;
 use tycho_simulation::tycho_client::feed::component_tracker::ComponentFilter;
-use types::ArbitrageGraph;
-use utils::constants::{TVL_LOWER_BOUND, TVL_UPPER_BOUND, TYCHO_API_KEY, network};
+use tycho_api::get_tokens;
+use types::{
+    AlertConfig, AlertEvent, ArbitrageGraph, BlockTiming, ChainConfig, Decision, ExecutionMode,
+    FlashExecutionPlan, GraphSnapshot, LegFill, Network, Opportunity, Protocol,
+};
+use utils::constants::{FEED_CAPACITY, TVL_LOWER_BOUND, TVL_UPPER_BOUND, TYCHO_API_KEY, network};
+use utils::rpc::{create_provider, create_signer};
 
 #[tokio::main]
 async fn main() {
-    setup_tracing();
+    let dashboard = std::env::args().nth(1).as_deref() == Some("dashboard");
+    let json_logs = std::env::args().any(|arg| arg == "--json-logs");
+    // set up before validation so that config errors are logged in the configured format
+    let logging = read_config().map(|config| config.logging).unwrap_or_default();
+    let _log_guard = setup_tracing(&logging, json_logs, dashboard);
+
+    if std::env::args().nth(1).as_deref() == Some("validate-config") {
+        run_validate_config();
+    }
+    if std::env::args().nth(1).as_deref() == Some("dump-graph") {
+        run_dump_graph().await;
+    }
 
-    let config = match load_config() {
+    let mut config = match load_config() {
         Ok(config) => {
             tracing::info!("Config loaded successfully: {:?}", config);
             config
@@ -35,73 +106,607 @@ async fn main() {
         }
     };
 
-    let mut stream_builders = vec![];
+    let signer = needs_signer(&config).then(|| create_signer(&config.private_key));
+    let owner = match signer.transpose() {
+        Ok(signer) => signer.map(|signer| signer.address()),
+        Err(e) => {
+            tracing::error!("Error loading signer: {}", e);
+            process::exit(1);
+        }
+    };
+
+    start_alerts(&config.alerts);
+
+    let journal = match Journal::open(&config.journal.path) {
+        Ok(journal) => Arc::new(journal),
+        Err(e) => {
+            tracing::error!("Error opening journal: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let feed = Feed::new(FEED_CAPACITY);
+    let (recorder, recorder_writer) =
+        match config.recorder.clone().map(PriceRecorder::start).transpose() {
+            Ok(started) => started.unzip(),
+            Err(e) => {
+                tracing::error!("Error starting price recorder: {}", e);
+                process::exit(1);
+            }
+        };
+    let arbitrage_graph = Arc::new(Mutex::new(ArbitrageGraph::new(asset_groups(&config))));
+    {
+        let mut graph = arbitrage_graph.lock().await;
+        graph.feed = Some(feed.clone());
+        graph.recorder = recorder;
+    }
+
+    // serve health and metrics while fetching tokens, readiness waits for the snapshots
+    let (snapshot_tx, snapshot_rx) = watch::channel(Arc::new(GraphSnapshot::default()));
+    // executable prices are only quoted for the dashboard
+    let reference_sizes =
+        if dashboard { config.dashboard.reference_sizes.clone() } else { HashMap::new() };
+    tokio::spawn(publish_snapshots(Arc::clone(&arbitrage_graph), reference_sizes, snapshot_tx));
+    let server_state = ServerState {
+        graph: snapshot_rx.clone(),
+        feed: feed.clone(),
+        networks: config.chains.iter().filter_map(configured_network).collect(),
+    };
+    tokio::spawn(run_server(config.server.listen.clone(), server_state));
+
+    let mut streams = vec![];
     let tvl_filter = ComponentFilter::with_tvl_range(TVL_LOWER_BOUND, TVL_UPPER_BOUND);
     let mut chain_tokens = HashMap::new();
+    let mut networks = vec![];
+    let inventory = Arc::new(Mutex::new(InventoryManager::new()));
+
+    for chain_config in config.chains.clone() {
+        let (network, tokens, stream) =
+            match setup_chain(chain_config.clone(), tvl_filter.clone()).await {
+                Ok(chain) => chain,
+                Err(e) => {
+                    tracing::error!("Error setting up chain {}: {}", chain_config.name, e);
+                    process::exit(1);
+                }
+            };
+        load_inventory(&inventory, &network, &chain_config, &tokens, owner).await;
 
-    for chain_config in config.chains {
-        tracing::info!("Processing chain: {}", chain_config.name);
+        chain_tokens.insert(network.chain, tokens);
+        streams.push((stream, network.clone()));
+        networks.push(network);
+    }
 
-        let chain = Chain::from_str(&chain_config.name).unwrap();
-        let network = network(chain_config.name.clone()).unwrap().clone();
-        let tokens = get_tokens(&network, TYCHO_API_KEY.to_string(), chain_config.tokens)
-            .await
-            .unwrap();
+    let token_addresses: HashMap<Chain, Vec<Bytes>> = chain_tokens
+        .iter()
+        .map(|(chain, tokens)| (*chain, tokens.keys().cloned().collect()))
+        .collect();
 
-        chain_tokens.insert(chain, tokens.clone());
+    arbitrage_graph.lock().await.initialize(chain_tokens);
 
-        let stream_builder = create_protocol_stream_builder(
-            network,
-            config.rpc_url.clone(),
-            tvl_filter.clone(),
-            TYCHO_API_KEY.to_string(),
-            tokens,
-        )
-        .await;
+    // a reloaded chain's tokens get their approvals without waiting for the next check
+    let (approval_tokens_tx, approval_tokens_rx) = watch::channel(token_addresses);
 
-        stream_builders.push((stream_builder, chain));
+    let (paper_trader, live) = match config.execution_mode {
+        ExecutionMode::Paper => {
+            tracing::info!("Running in paper trading mode");
+            (Some(Arc::new(Mutex::new(PaperTrader::new(feed.clone())))), None)
+        }
+        ExecutionMode::Live => {
+            let private_key = &config.private_key;
+            match setup_live_execution(&networks, private_key, approval_tokens_rx, &journal) {
+                Ok(live) => (None, Some(Arc::new(live))),
+                Err(e) => {
+                    tracing::error!("Error setting up execution: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+    };
+
+    // risk changes from the config reload reach every chain task without restarting it
+    let (risk_tx, risk_rx) = watch::channel(config.risk_param);
+    let (networks_tx, networks_rx) = watch::channel(networks.clone());
+
+    let context = StreamContext {
+        arbitrage_graph: Arc::clone(&arbitrage_graph),
+        paper_trader,
+        live,
+        inventory: Arc::clone(&inventory),
+        journal: Arc::clone(&journal),
+        feed: feed.clone(),
+        networks: networks_rx,
+        risk_param: risk_rx,
+        alerts: config.alerts.clone(),
+    };
+
+    let mut tasks = HashMap::new();
+
+    for (stream, network) in streams {
+        let chain = network.chain;
+        let task = spawn_chain_stream(stream, network, context.clone());
+        tasks.insert(chain, task);
     }
 
-    let arbitrage_graph = Arc::new(Mutex::new(ArbitrageGraph::new()));
-    {
-        let mut graph = arbitrage_graph.lock().await;
-        graph.initialize(chain_tokens);
+    tokio::spawn(run_rebalancer(
+        Arc::clone(&arbitrage_graph),
+        Arc::clone(&inventory),
+        inventory_targets(&config),
+        config.rebalance.clone(),
+    ));
+
+    // quitting the dashboard shuts down like ctrl-c, only the sender is dropped without it
+    let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
+    if dashboard {
+        let graph = snapshot_rx.clone();
+        let reference_sizes = config.dashboard.reference_sizes.clone();
+        let networks = networks.clone();
+        let events = feed.subscribe();
+
+        std::thread::spawn(move || {
+            if let Err(e) = run_dashboard(graph, reference_sizes, networks, events) {
+                tracing::error!("Dashboard failed: {}", e);
+            }
+            let _ = shutdown_tx.blocking_send(());
+        });
     }
 
-    let mut tasks = vec![];
+    let (config_tx, mut config_rx) = mpsc::channel(1);
+    tokio::spawn(watch_config(config_tx));
+
+    loop {
+        tokio::select! {
+            Some(new_config) = config_rx.recv() => {
+                let diff = diff_config(&config, &new_config);
+
+                if let Some(risk_param) = diff.risk_param {
+                    tracing::info!("Risk param changed: {} -> {}", config.risk_param, risk_param);
+                    config.risk_param = risk_param;
+                    risk_tx.send_replace(risk_param);
+                }
 
-    for (stream_builder, chain) in stream_builders {
-        let arbitrage_graph = Arc::clone(&arbitrage_graph);
-        let task = tokio::spawn(async move {
-            let mut stream = stream_builder
-                .build()
-                .await
-                .expect("Failed building protocol stream");
+                for chain_config in diff.reloaded_chains {
+                    tracing::info!("Restarting stream for chain: {}", chain_config.name);
+
+                    // the old stream keeps running until its replacement is connected
+                    let (mut network, tokens, stream) =
+                        match setup_chain(chain_config.clone(), tvl_filter.clone()).await {
+                            Ok(chain) => chain,
+                            Err(e) => {
+                                tracing::error!(
+                                    "Keeping the running stream for {}: {}",
+                                    chain_config.name,
+                                    e
+                                );
+                                continue;
+                            }
+                        };
+                    let chain = network.chain;
+
+                    // the executor and approvals were built for these, changing them needs a
+                    // restart (see diff_config)
+                    if let Some(running) = networks.iter().find(|n| n.chain == chain) {
+                        network.transfer_mode = running.transfer_mode;
+                        network.flash_executor = running.flash_executor.clone();
+                    }
+                    networks_tx.send_modify(|networks| {
+                        if let Some(running) = networks.iter_mut().find(|n| n.chain == chain) {
+                            *running = network.clone();
+                        }
+                    });
+                    approval_tokens_tx.send_modify(|approval_tokens| {
+                        approval_tokens.insert(chain, tokens.keys().cloned().collect());
+                    });
+
+                    if let Some(task) = tasks.remove(&chain) {
+                        task.abort();
+                    }
+                    load_inventory(&inventory, &network, &chain_config, &tokens, owner).await;
 
-            while let Some(message_result) = stream.next().await {
-                match message_result {
-                    Ok(msg) => {
+                    {
                         let mut graph = arbitrage_graph.lock().await;
-                        graph.handle_block_update(msg, chain);
+                        graph.sync_chain_tokens(chain, tokens);
                     }
-                    Err(e) => {
-                        tracing::error!(
-                            "Error receiving message: {e:?}. Continuing to next message..."
-                        );
-                        continue;
+
+                    let task = spawn_chain_stream(stream, network, context.clone());
+                    tasks.insert(chain, task);
+
+                    let running = config.chains.iter_mut().find(|c| c.name == chain_config.name);
+                    *running.unwrap() = chain_config;
+                }
+            }
+            _ = select_all(tasks.values_mut()) => {
+                tracing::error!("Chain stream terminated, shutting down");
+                break;
+            }
+            _ = tokio::signal::ctrl_c() => {
+                tracing::info!("Interrupted, shutting down");
+                break;
+            }
+            Some(()) = shutdown_rx.recv() => {
+                tracing::info!("Dashboard closed, shutting down");
+                break;
+            }
+        }
+    }
+
+    if let Some(writer) = recorder_writer {
+        stop_recorder(&arbitrage_graph, writer).await;
+    }
+}
+
+/// `validate-config` mode: prints every config issue as a table and exits.
+fn run_validate_config() -> ! {
+    let config = match read_config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error reading config: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let issues = validate_config(&config);
+    if issues.is_empty() {
+        println!("Config is valid");
+        process::exit(0);
+    }
+
+    print!("{}", format_issues(&issues));
+    println!("{} issue(s) found", issues.len());
+    process::exit(1);
+}
+
+/// `dump-graph [--format dot|graphml] [--chain <name>] [--symbol <symbol>]` mode: prints the
+/// graph of the instance running with this config, fetched from its HTTP server.
+async fn run_dump_graph() -> ! {
+    let config = match read_config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error reading config: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let args = std::env::args().skip(2).collect::<Vec<_>>();
+    let mut query = vec![];
+    for pair in args.chunks(2) {
+        match pair {
+            [flag, value] if ["--format", "--chain", "--symbol"].contains(&flag.as_str()) => {
+                query.push((flag.trim_start_matches("--").to_string(), value.clone()));
+            }
+            _ => {
+                eprintln!("Unexpected arguments: {}", pair.join(" "));
+                process::exit(1);
+            }
+        }
+    }
+
+    let listen = config.server.listen.replace("0.0.0.0", "127.0.0.1");
+    let response = reqwest::Client::new()
+        .get(format!("http://{}/graph", listen))
+        .query(&query)
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => {
+            print!("{}", response.text().await.unwrap_or_default());
+            process::exit(0);
+        }
+        Ok(response) => {
+            let status = response.status();
+            eprintln!("{}: {}", status, response.text().await.unwrap_or_default());
+        }
+        Err(e) => eprintln!("Failed to fetch graph from {}: {}", listen, e),
+    }
+    process::exit(1);
+}
+
+#[derive(Clone)]
+struct StreamContext {
+    arbitrage_graph: Arc<Mutex<ArbitrageGraph>>,
+    paper_trader: Option<Arc<Mutex<PaperTrader>>>,
+    live: Option<Arc<LiveExecutor>>,
+    inventory: Arc<Mutex<InventoryManager>>,
+    journal: Arc<Journal>,
+    feed: Feed,
+    /// Replaced per chain when a reload rebuilds its stream
+    networks: watch::Receiver<Vec<Network>>,
+    risk_param: watch::Receiver<u64>,
+    alerts: AlertConfig,
+}
+
+/// The chain's network with the overrides from its config applied.
+fn configured_network(chain_config: &ChainConfig) -> Option<Network> {
+    let mut network = network(chain_config.name.clone())?;
+    if let Some(rpc_url) = &chain_config.rpc_url {
+        network.rpc = rpc_url.clone();
+    }
+    network.transfer_mode = chain_config.transfer_mode;
+    network.flash_executor = chain_config.flash_executor.clone();
+    network.stale_after_blocks = chain_config.stale_after_blocks;
+
+    Some(network)
+}
+
+#[tracing::instrument(skip_all, fields(chain = %chain_config.name))]
+/// Fetches the chain's tokens and connects its protocol stream.
+async fn setup_chain(
+    chain_config: ChainConfig,
+    tvl_filter: ComponentFilter,
+) -> Result<
+    (Network, HashMap<Bytes, Token>, impl Stream<Item = Result<BlockUpdate, impl Debug>> + Unpin),
+    anyhow::Error,
+> {
+    tracing::info!("Processing chain: {}", chain_config.name);
+
+    let network = configured_network(&chain_config)
+        .ok_or_else(|| anyhow::anyhow!("Unknown chain: {}", chain_config.name))?;
+    let protocols = chain_config
+        .protocols
+        .iter()
+        .map(|p| Protocol::from_str(p).ok_or_else(|| anyhow::anyhow!("Unknown protocol: {}", p)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let tokens = get_tokens(&network, TYCHO_API_KEY.to_string(), chain_config.tokens).await?;
+
+    let stream = create_protocol_stream_builder(
+        network.clone(),
+        tvl_filter,
+        TYCHO_API_KEY.to_string(),
+        tokens.clone(),
+        protocols,
+    )
+    .await
+    .build()
+    .instrument(tracing::info_span!("build_stream", chain = %network.name))
+    .await
+    .map_err(|e| anyhow::anyhow!("Failed building protocol stream: {:?}", e))?;
+
+    Ok((network, tokens, stream))
+}
+
+fn setup_live_execution(
+    networks: &[Network],
+    private_key: &str,
+    token_addresses: watch::Receiver<HashMap<Chain, Vec<Bytes>>>,
+    journal: &Arc<Journal>,
+) -> Result<LiveExecutor, anyhow::Error> {
+    let mut tx_managers = HashMap::new();
+    for network in networks {
+        let tx_manager = TxManager::new(network, private_key)?;
+        tokio::spawn(run_tx_journal(Arc::clone(journal), tx_manager.subscribe()));
+        tx_managers.insert(network.chain, Arc::new(tx_manager));
+    }
+
+    let approvals = Arc::new(ApprovalManager::new(networks, private_key, &tx_managers)?);
+    tokio::spawn(run_approvals(Arc::clone(&approvals), token_addresses));
+
+    LiveExecutor::new(networks, private_key, approvals, tx_managers)
+}
+
+async fn load_inventory(
+    inventory: &Mutex<InventoryManager>,
+    network: &Network,
+    chain_config: &ChainConfig,
+    tokens: &HashMap<Bytes, Token>,
+    owner: Option<Address>,
+) {
+    match fetch_chain_balances(network, &chain_config.tokens, tokens, owner).await {
+        Ok(balances) => inventory.lock().await.set_chain_balances(network.chain, balances),
+        Err(e) => tracing::error!("Failed to load inventory for {}: {}", network.name, e),
+    }
+}
+
+fn spawn_chain_stream<S, E>(
+    mut stream: S,
+    network: Network,
+    context: StreamContext,
+) -> JoinHandle<()>
+where
+    S: Stream<Item = Result<BlockUpdate, E>> + Unpin + Send + 'static,
+    E: Debug,
+{
+    let chain = network.chain;
+    let label = chain_label(chain);
+    let clock = create_provider(&network)
+        .inspect_err(|e| tracing::warn!("No provider for {}, block lag not recorded: {}", label, e))
+        .ok()
+        .map(|provider| BlockClock::new(provider, &network));
+    let span = tracing::info_span!("chain_stream", chain = %label);
+
+    let task = async move {
+        let mut errors = ErrorWindow::new(&context.alerts);
+
+        while let Some(message_result) = stream.next().await {
+            match message_result {
+                Ok(msg) => {
+                    let timing = BlockTiming {
+                        chain,
+                        block: msg.block_number,
+                        received_at: Instant::now(),
+                        received_at_system: SystemTime::now(),
+                    };
+                    if let Some(clock) = &clock {
+                        clock.record_block_lag(timing);
+                    }
+
+                    BLOCK_UPDATES.with_label_values(&[&label]).inc();
+                    NEW_PAIRS.with_label_values(&[&label]).inc_by(msg.new_pairs.len() as u64);
+                    REMOVED_PAIRS
+                        .with_label_values(&[&label])
+                        .inc_by(msg.removed_pairs.len() as u64);
+
+                    let pools_changed = !msg.new_pairs.is_empty() || !msg.removed_pairs.is_empty();
+                    let mut graph = timed_lock("graph", &context.arbitrage_graph).await;
+                    observe_stage(&timing, "graph_lock");
+                    let start = Instant::now();
+                    graph.handle_block_update(msg, timing);
+                    if pools_changed {
+                        graph.refresh_flash_entries(&network);
+                    }
+                    BLOCK_UPDATE_SECONDS
+                        .with_label_values(&[&label])
+                        .observe(start.elapsed().as_secs_f64());
+                    observe_stage(&timing, "graph_update");
+                    GRAPH_NODES.set(graph.graph.node_count() as i64);
+                    GRAPH_EDGES.set(graph.graph.edge_count() as i64);
+
+                    let cycles = detect_cycles(&graph, chain);
+                    observe_stage(&timing, "detection");
+
+                    if let Some(paper_trader) = &context.paper_trader {
+                        let fills = timed_lock("paper_trader", paper_trader)
+                            .await
+                            .on_block(chain, &graph);
+                        let mut inventory = timed_lock("inventory", &context.inventory).await;
+                        for fill in &fills {
+                            inventory.settle(fill);
+                            record_paper_fill(&context.journal, fill);
+                        }
+                        observe_stage(&timing, "paper_fill");
+                    }
+                    drop(graph);
+
+                    if !cycles.is_empty() {
+                        process_cycles(&context, cycles, timing).await;
                     }
-                };
+                }
+                Err(e) => {
+                    STREAM_ERRORS.with_label_values(&[&label]).inc();
+                    tracing::error!(
+                        "Error receiving message: {e:?}. Continuing to next message..."
+                    );
+                    if let Some(count) = errors.record() {
+                        alert(AlertEvent::IngestionErrors {
+                            chain,
+                            errors: count,
+                            window_secs: errors.window_secs(),
+                        });
+                    }
+                    continue;
+                }
+            };
+        }
+
+        tracing::error!("Stream for {} ended", label);
+        alert(AlertEvent::StreamDisconnected { chain });
+    };
+
+    tokio::spawn(task.instrument(span))
+}
+
+/// Sizes the cycles detected on a block, bounds their slippage and hands the accepted ones to
+/// execution. Pool simulations run on the cycles' cloned states, outside the graph lock.
+async fn process_cycles(context: &StreamContext, cycles: Vec<Cycle>, timing: BlockTiming) {
+    let opportunities = {
+        let inventory = timed_lock("inventory", &context.inventory).await;
+        cycles
+            .iter()
+            .filter_map(|cycle| size_cycle(cycle, &inventory, timing))
+            .collect::<Vec<_>>()
+    };
+    observe_stage(&timing, "sizing");
+
+    let risk_param = *context.risk_param.borrow();
+    let networks = context.networks.borrow().clone();
+    for mut opportunity in opportunities {
+        let prepared = {
+            let graph = timed_lock("graph", &context.arbitrage_graph).await;
+            apply_slippage_bounds(&graph, &networks, risk_param, &mut opportunity)
+                .and_then(|_| flash_plan(&opportunity))
+        };
+
+        context.journal.record_opportunity(&opportunity);
+        let plan = match prepared {
+            Ok(plan) => plan,
+            Err(reason) => {
+                tracing::debug!("Rejecting opportunity: {}", reason);
+                context.journal.record_decision(&opportunity.id, &Decision::Rejected { reason });
+                continue;
             }
-        });
+        };
 
-        tasks.push(task);
+        // flash-funded opportunities borrow their input instead of reserving inventory
+        if plan.is_none() {
+            let reserved =
+                timed_lock("inventory", &context.inventory).await.reserve(&opportunity);
+            if let Err(reason) = reserved {
+                context.journal.record_decision(&opportunity.id, &Decision::Rejected { reason });
+                continue;
+            }
+        }
+
+        if let Some(paper_trader) = &context.paper_trader {
+            timed_lock("paper_trader", paper_trader).await.submit(opportunity);
+        } else if let Some(live) = &context.live {
+            opportunity_accepted();
+            context.feed.publish_opportunity(&opportunity);
+            let live = Arc::clone(live);
+            tokio::spawn(execute_live(live, context.clone(), opportunity, plan));
+        }
     }
+}
 
-    let _ = select_all(tasks).await;
+/// Borrow, swap and repay plan of an opportunity entered through a flash-loan source, `None`
+/// for one funded from inventory.
+fn flash_plan(opportunity: &Opportunity) -> Result<Option<FlashExecutionPlan>, String> {
+    let Some(source) = &opportunity.flash else {
+        return Ok(None);
+    };
+
+    plan_flash_execution(opportunity, source).map(Some)
+}
+
+/// Runs an accepted opportunity to completion off the chain task, then settles its fills or
+/// releases its reservation.
+async fn execute_live(
+    live: Arc<LiveExecutor>,
+    context: StreamContext,
+    opportunity: Opportunity,
+    flash_plan: Option<FlashExecutionPlan>,
+) {
+    let result = match &flash_plan {
+        Some(plan) => live.execute_flash(&opportunity, plan).await,
+        None => live.execute(&opportunity).await,
+    };
+    let fills = match result {
+        Ok(fills) => fills,
+        Err(reason) => {
+            tracing::warn!(opportunity_id = %opportunity.id, "Not executed: {}", reason);
+            timed_lock("inventory", &context.inventory).await.release(&opportunity.id);
+            context.journal.record_decision(&opportunity.id, &Decision::Rejected { reason });
+            return;
+        }
+    };
+
+    {
+        let mut inventory = timed_lock("inventory", &context.inventory).await;
+        for fill in &fills {
+            inventory.settle(fill);
+        }
+    }
+
+    for fill in &fills {
+        context.journal.record_fill(fill);
+    }
+
+    let decision = match fills.iter().find(|fill| !fill.filled) {
+        None => Decision::Executed,
+        Some(fill) => Decision::Rejected {
+            reason: format!("{:?} leg was not included", fill.leg.chain),
+        },
+    };
+    context.journal.record_decision(&opportunity.id, &decision);
 }
 
-pub fn setup_tracing() {
-    let filter =
-        tracing_subscriber::EnvFilter::from_default_env().add_directive("info".parse().unwrap()); // Default to info level if RUST_LOG is not set
-    tracing_subscriber::fmt().with_env_filter(filter).init();
+fn record_paper_fill(journal: &Journal, fill: &LegFill) {
+    let decision = if fill.filled {
+        Decision::PaperFilled
+    } else {
+        Decision::Rejected {
+            reason: format!("paper fill failed on {:?}", fill.leg.chain),
+        }
+    };
+
+    journal.record_fill(fill);
+    journal.record_decision(&fill.opportunity_id, &decision);
 }
@@ -0,0 +1,124 @@
+use prometheus::{
+    Encoder, HistogramVec, IntCounterVec, IntGauge, TextEncoder, register_histogram_vec,
+    register_int_counter_vec, register_int_gauge,
+};
+use std::sync::LazyLock;
+use std::time::Instant;
+use tokio::sync::{Mutex, MutexGuard};
+use tycho_common::models::Chain;
+
+pub static BLOCK_UPDATES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("block_updates_total", "Block updates received", &["chain"])
+        .unwrap()
+});
+
+pub static NEW_PAIRS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("new_pairs_total", "Pairs added by block updates", &["chain"])
+        .unwrap()
+});
+
+pub static REMOVED_PAIRS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("removed_pairs_total", "Pairs removed by block updates", &["chain"])
+        .unwrap()
+});
+
+pub static STREAM_ERRORS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("stream_errors_total", "Errors received from streams", &["chain"])
+        .unwrap()
+});
+
+pub static GRAPH_NODES: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("graph_nodes", "Token nodes in the arbitrage graph").unwrap()
+});
+
+pub static GRAPH_EDGES: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("graph_edges", "Price edges in the arbitrage graph").unwrap()
+});
+
+pub static BLOCK_UPDATE_SECONDS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "block_update_seconds",
+        "Time spent applying a block update to the graph",
+        &["chain"],
+        prometheus::exponential_buckets(0.0001, 2.0, 16).unwrap()
+    )
+    .unwrap()
+});
+
+/// Time from pulling a block update off the stream until the end of each processing stage.
+pub static STAGE_LATENCY_SECONDS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "stage_latency_seconds",
+        "Time since block update receipt at the end of each stage",
+        &["chain", "stage"],
+        prometheus::exponential_buckets(0.0001, 2.0, 18).unwrap()
+    )
+    .unwrap()
+});
+
+pub static BLOCK_LAG_SECONDS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "block_lag_seconds",
+        "Time between the block timestamp and receipt of its update",
+        &["chain"],
+        vec![0.1, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0]
+    )
+    .unwrap()
+});
+
+/// `outcome` is one of detected, accepted or rejected; `reason` is only set for rejections.
+pub static OPPORTUNITIES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "opportunities_total",
+        "Opportunities by outcome",
+        &["outcome", "reason"]
+    )
+    .unwrap()
+});
+
+pub static LOCK_WAIT_SECONDS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "lock_wait_seconds",
+        "Time spent waiting for shared state locks",
+        &["lock"],
+        prometheus::exponential_buckets(0.00001, 4.0, 12).unwrap()
+    )
+    .unwrap()
+});
+
+pub fn chain_label(chain: Chain) -> String {
+    format!("{:?}", chain).to_lowercase()
+}
+
+pub fn opportunity_detected() {
+    OPPORTUNITIES.with_label_values(&["detected", ""]).inc();
+}
+
+pub fn opportunity_accepted() {
+    OPPORTUNITIES.with_label_values(&["accepted", ""]).inc();
+}
+
+pub fn opportunity_rejected(reason: &str) {
+    OPPORTUNITIES.with_label_values(&["rejected", reason]).inc();
+}
+
+/// Locks `mutex`, recording how long the wait took under `name`.
+pub async fn timed_lock<'a, T>(name: &str, mutex: &'a Mutex<T>) -> MutexGuard<'a, T> {
+    let start = Instant::now();
+    let guard = mutex.lock().await;
+    LOCK_WAIT_SECONDS
+        .with_label_values(&[name])
+        .observe(start.elapsed().as_secs_f64());
+
+    guard
+}
+
+/// Prometheus text exposition of every registered metric.
+pub async fn metrics_handler() -> String {
+    let mut buffer = vec![];
+    if let Err(e) = TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+        tracing::error!("Failed to encode metrics: {}", e);
+    }
+
+    String::from_utf8(buffer).unwrap_or_default()
+}
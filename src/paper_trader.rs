@@ -0,0 +1,181 @@
+use crate::feed::Feed;
+use crate::metrics::opportunity_accepted;
+use crate::types::{ArbitrageGraph, LegFill, Opportunity, OpportunityLeg};
+use crate::utils::units::to_signed_units;
+use num_bigint::{BigInt, BigUint};
+use std::collections::HashMap;
+use tycho_common::Bytes;
+use tycho_common::models::Chain;
+use tycho_simulation::models::Token;
+
+type TokenKey = (Chain, Bytes);
+
+struct PendingOpportunity {
+    /// Legs not filled yet, with their position in the opportunity
+    legs: Vec<(usize, OpportunityLeg)>,
+    flows: HashMap<TokenKey, BigInt>,
+    /// Fee of the flash loan funding the opportunity, in millionths of the borrowed amount
+    flash_fee_pips: Option<u32>,
+}
+
+/// Dry-run execution: accepted opportunities are filled against the pool states of the next
+/// block received on each leg's chain instead of being sent on-chain.
+pub struct PaperTrader {
+    pending: HashMap<String, PendingOpportunity>,
+    tokens: HashMap<TokenKey, Token>,
+    balances: HashMap<TokenKey, BigInt>,
+    realized_pnl: HashMap<TokenKey, BigInt>,
+    feed: Feed,
+}
+
+impl PaperTrader {
+    pub fn new(feed: Feed) -> Self {
+        Self {
+            pending: HashMap::new(),
+            tokens: HashMap::new(),
+            balances: HashMap::new(),
+            realized_pnl: HashMap::new(),
+            feed,
+        }
+    }
+
+    pub fn submit(&mut self, opportunity: Opportunity) {
+        tracing::info!(opportunity_id = %opportunity.id, "Paper trading opportunity");
+        opportunity_accepted();
+        self.feed.publish_opportunity(&opportunity);
+
+        self.pending.insert(
+            opportunity.id,
+            PendingOpportunity {
+                legs: opportunity.legs.into_iter().enumerate().collect(),
+                flows: HashMap::new(),
+                flash_fee_pips: opportunity.flash.map(|source| source.fee_pips),
+            },
+        );
+    }
+
+    /// Fills every pending leg on `chain`, must be called after the block update was applied.
+    pub fn on_block(&mut self, chain: Chain, graph: &ArbitrageGraph) -> Vec<LegFill> {
+        let mut fills = vec![];
+        let mut completed = vec![];
+
+        for (id, opportunity) in self.pending.iter_mut() {
+            let (legs, remaining): (Vec<_>, Vec<_>) =
+                opportunity.legs.drain(..).partition(|(_, leg)| leg.chain == chain);
+            opportunity.legs = remaining;
+
+            for (leg_index, leg) in legs {
+                let fill = fill_leg(graph, &leg);
+
+                match &fill {
+                    Ok(amount_out) => {
+                        let first = leg.hops.first().unwrap();
+                        let last = leg.hops.last().unwrap();
+
+                        tracing::info!(
+                            opportunity_id = %id,
+                            chain = ?chain,
+                            "Paper filled: {} {} -> {} {} (expected {})",
+                            leg.amount_in(),
+                            first.from_token.symbol,
+                            amount_out,
+                            last.to_token.symbol,
+                            leg.amount_out()
+                        );
+
+                        let spent = (chain, first.from_token.address.clone());
+                        let received = (chain, last.to_token.address.clone());
+                        let amount_in = BigInt::from(leg.amount_in());
+                        let amount_out = BigInt::from(amount_out.clone());
+
+                        *opportunity.flows.entry(spent.clone()).or_default() -= &amount_in;
+                        *opportunity.flows.entry(received.clone()).or_default() += &amount_out;
+
+                        // a flash loan costs its fee and leaves the signer's balances untouched
+                        match opportunity.flash_fee_pips {
+                            Some(fee_pips) => {
+                                let fee = &amount_in * fee_pips / 1_000_000u32;
+                                *opportunity.flows.entry(spent.clone()).or_default() -= fee;
+                            }
+                            None => {
+                                *self.balances.entry(spent.clone()).or_default() -= amount_in;
+                                *self.balances.entry(received.clone()).or_default() += amount_out;
+                            }
+                        }
+
+                        self.tokens.insert(spent, first.from_token.clone());
+                        self.tokens.insert(received, last.to_token.clone());
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            opportunity_id = %id,
+                            chain = ?chain,
+                            "Could not paper fill: {}",
+                            e
+                        );
+                    }
+                }
+
+                fills.push(LegFill {
+                    opportunity_id: id.clone(),
+                    leg_index,
+                    leg,
+                    filled: fill.is_ok(),
+                    flash: opportunity.flash_fee_pips.is_some(),
+                    amount_out: fill.ok(),
+                    simulated_gas: None,
+                });
+            }
+
+            if opportunity.legs.is_empty() {
+                completed.push(id.clone());
+            }
+        }
+
+        for id in completed {
+            let opportunity = self.pending.remove(&id).unwrap();
+
+            for (key, flow) in opportunity.flows {
+                *self.realized_pnl.entry(key).or_default() += flow;
+            }
+
+            self.log_summary();
+        }
+
+        fills
+    }
+
+    pub fn log_summary(&self) {
+        for (key, pnl) in &self.realized_pnl {
+            let token = &self.tokens[key];
+            let balance = self.balances.get(key).cloned().unwrap_or_default();
+
+            tracing::info!(
+                "Paper PnL {} on {:?}: realized {:.6}, balance {:.6}",
+                token.symbol,
+                key.0,
+                to_signed_units(pnl, token.decimals),
+                to_signed_units(&balance, token.decimals)
+            );
+        }
+    }
+}
+
+/// Runs the leg's hops on the current pool states, chaining each hop's output into the next.
+fn fill_leg(graph: &ArbitrageGraph, leg: &OpportunityLeg) -> Result<BigUint, String> {
+    let mut amount = leg.amount_in();
+
+    for hop in &leg.hops {
+        let edge = graph
+            .find_edge(&hop.pool_address, &hop.from_token.address)
+            .ok_or_else(|| format!("pool {} no longer tracked", hop.pool_address))?;
+
+        amount = edge
+            .state
+            .get_amount_out(amount, &hop.from_token, &hop.to_token)
+            .map_err(|e| format!("simulation failed on {}: {:?}", hop.pool_address, e))?
+            .amount;
+    }
+
+    Ok(amount)
+}
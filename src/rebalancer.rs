@@ -0,0 +1,157 @@
+use crate::inventory::InventoryManager;
+use crate::metrics::timed_lock;
+use crate::types::{
+    ArbitrageGraph, BridgeConfig, BridgeTransfer, RebalanceConfig, RebalancePlan, TradePreference,
+};
+use crate::utils::units::to_units;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tycho_common::Bytes;
+use tycho_common::models::Chain;
+use tycho_simulation::models::Token;
+
+struct Drift {
+    chain: Chain,
+    token: Token,
+    /// Balance minus target, in token units
+    amount: f64,
+}
+
+fn bridge_cost(bridges: &[BridgeConfig], from: Chain, to: Chain, amount: f64) -> Option<f64> {
+    bridges
+        .iter()
+        .find(|b| {
+            Chain::from_str(&b.from).ok() == Some(from) && Chain::from_str(&b.to).ok() == Some(to)
+        })
+        .map(|b| b.fixed_fee + amount * b.fee_bps as f64 / 10_000.0)
+}
+
+/// Compares balances against their targets for every asset and pairs chains holding too much
+/// with chains holding too little. A pair becomes a bridge transfer when a configured bridge
+/// costs less than the amount moved, otherwise a trade preference.
+pub fn plan_rebalance(
+    graph: &ArbitrageGraph,
+    inventory: &InventoryManager,
+    targets: &HashMap<(Chain, Bytes), f64>,
+    config: &RebalanceConfig,
+) -> RebalancePlan {
+    let mut plan = RebalancePlan::default();
+
+    for node in graph.graph.node_weights() {
+        let mut surpluses = vec![];
+        let mut deficits = vec![];
+
+        for (chain, tokens) in &node.tokens {
+            for token in tokens {
+                let Some(target) = targets.get(&(*chain, token.address.clone())) else {
+                    continue;
+                };
+
+                let balance = to_units(&inventory.balance(*chain, &token.address), token.decimals);
+                let drift = Drift {
+                    chain: *chain,
+                    token: token.clone(),
+                    amount: balance - target,
+                };
+
+                if drift.amount > target * config.tolerance {
+                    surpluses.push(drift);
+                } else if -drift.amount > target * config.tolerance {
+                    deficits.push(drift);
+                }
+            }
+        }
+
+        surpluses.sort_by(|a, b| b.amount.total_cmp(&a.amount));
+        deficits.sort_by(|a, b| a.amount.total_cmp(&b.amount));
+
+        for surplus in surpluses.iter_mut() {
+            for deficit in deficits.iter_mut() {
+                let amount = surplus.amount.min(-deficit.amount);
+                if amount <= 0.0 || surplus.chain == deficit.chain {
+                    continue;
+                }
+
+                match bridge_cost(&config.bridges, surplus.chain, deficit.chain, amount) {
+                    Some(cost) if cost < amount => plan.transfers.push(BridgeTransfer {
+                        asset: node.symbol.clone(),
+                        from_chain: surplus.chain,
+                        from_token: surplus.token.clone(),
+                        to_chain: deficit.chain,
+                        to_token: deficit.token.clone(),
+                        amount,
+                        estimated_cost: cost,
+                    }),
+                    _ => plan.preferences.push(TradePreference {
+                        asset: node.symbol.clone(),
+                        sell_on: surplus.chain,
+                        buy_on: deficit.chain,
+                        amount,
+                    }),
+                }
+
+                surplus.amount -= amount;
+                deficit.amount += amount;
+            }
+        }
+    }
+
+    plan
+}
+
+pub fn log_plan(plan: &RebalancePlan) {
+    if plan.transfers.is_empty() && plan.preferences.is_empty() {
+        tracing::info!("Inventory within targets, nothing to rebalance");
+        return;
+    }
+
+    for transfer in &plan.transfers {
+        tracing::info!(
+            "Rebalance: bridge {:.6} {} from {:?} to {:?} (est. cost {:.6})",
+            transfer.amount,
+            transfer.from_token.symbol,
+            transfer.from_chain,
+            transfer.to_chain,
+            transfer.estimated_cost
+        );
+    }
+
+    for preference in &plan.preferences {
+        tracing::info!(
+            "Rebalance: prefer trades selling {:.6} {} on {:?} and buying on {:?}",
+            preference.amount,
+            preference.asset,
+            preference.sell_on,
+            preference.buy_on
+        );
+    }
+}
+
+/// Periodically plans and reports a rebalance. Executing the transfers is left to the operator.
+pub async fn run_rebalancer(
+    arbitrage_graph: Arc<Mutex<ArbitrageGraph>>,
+    inventory: Arc<Mutex<InventoryManager>>,
+    targets: HashMap<(Chain, Bytes), f64>,
+    config: RebalanceConfig,
+) {
+    if targets.is_empty() {
+        return;
+    }
+
+    let mut interval = tokio::time::interval(Duration::from_secs(config.interval_secs));
+
+    loop {
+        interval.tick().await;
+
+        let plan = {
+            let graph = timed_lock("graph", &arbitrage_graph).await;
+            let inventory = timed_lock("inventory", &inventory).await;
+            plan_rebalance(&graph, &inventory, &targets, &config)
+        };
+
+        log_plan(&plan);
+    }
+}
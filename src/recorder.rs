@@ -0,0 +1,383 @@
+use crate::metrics::chain_label;
+use crate::types::{ArbitrageGraph, LogRotation, PriceEdge, RecordFormat, RecorderConfig, Symbol};
+use crate::utils::constants::{RECORDER_CAPACITY, RECORDER_PARQUET_ROWS};
+use crate::utils::units::{from_units, to_units};
+use arrow::array::{ArrayRef, Float64Builder, StringBuilder, UInt64Builder};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, Utc};
+use parquet::arrow::ArrowWriter;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use tokio::sync::{Mutex, mpsc};
+use tokio::sync::mpsc::error::TrySendError;
+
+/// One edge update, with the executable price at each reference size of the input asset.
+#[derive(Debug, Clone)]
+pub struct PriceRecord {
+    pub timestamp: DateTime<Utc>,
+    pub chain: String,
+    pub block: u64,
+    pub pool: String,
+    pub protocol: String,
+    pub from_symbol: String,
+    pub from_address: String,
+    pub to_symbol: String,
+    pub to_address: String,
+    pub spot_price: f64,
+    /// `(size, price)`, price is None when the simulation failed
+    pub quotes: Vec<(f64, Option<f64>)>,
+}
+
+/// Edge update as queued under the graph lock, with a copy of the pool state to quote from.
+struct EdgeUpdate {
+    timestamp: DateTime<Utc>,
+    block: u64,
+    edge: PriceEdge,
+    from_symbol: Symbol,
+    to_symbol: Symbol,
+}
+
+/// Hands edge updates to a writer thread, which quotes them and does the file IO. Under the
+/// graph lock an update only costs a copy of the edge.
+#[derive(Debug, Clone)]
+pub struct PriceRecorder {
+    sender: mpsc::Sender<EdgeUpdate>,
+}
+
+impl PriceRecorder {
+    /// Starts the writer thread. It runs until every recorder is dropped, then writes out what
+    /// is still queued or buffered and exits, so join it before exiting the process.
+    pub fn start(config: RecorderConfig) -> Result<(Self, JoinHandle<()>), anyhow::Error> {
+        std::fs::create_dir_all(&config.directory)?;
+
+        let (sender, receiver) = mpsc::channel(RECORDER_CAPACITY);
+        let columns = config.reference_sizes.values().map(Vec::len).max().unwrap_or(0);
+        let writer = RecordWriter {
+            directory: PathBuf::from(&config.directory),
+            format: config.format,
+            rotation: config.rotation,
+            reference_sizes: config.reference_sizes,
+            columns,
+        };
+        let handle = std::thread::spawn(move || writer.run(receiver));
+
+        Ok((Self { sender }, handle))
+    }
+
+    /// Queues the edge for the writer. Updates are dropped, with a warning, while the writer
+    /// is behind.
+    pub fn record(&self, graph: &ArbitrageGraph, edge: &PriceEdge, block: u64) {
+        let (from_symbol, _) = graph.asset_for(edge.chain, &edge.from_token);
+        let (to_symbol, _) = graph.asset_for(edge.chain, &edge.to_token);
+
+        let update = EdgeUpdate {
+            timestamp: Utc::now(),
+            block,
+            edge: edge.clone(),
+            from_symbol,
+            to_symbol,
+        };
+
+        match self.sender.try_send(update) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => tracing::warn!("Price recorder behind, dropping record"),
+            Err(TrySendError::Closed(_)) => tracing::error!("Price recorder writer has stopped"),
+        }
+    }
+}
+
+/// Detaches the recorder from the graph and waits for its writer to write out every queued
+/// update, Parquet rows are only written in batches.
+pub async fn stop_recorder(arbitrage_graph: &Mutex<ArbitrageGraph>, writer: JoinHandle<()>) {
+    arbitrage_graph.lock().await.recorder = None;
+
+    tracing::info!("Writing out queued price records");
+    match tokio::task::spawn_blocking(move || writer.join()).await {
+        Ok(Ok(())) => {}
+        _ => tracing::error!("Price recorder writer failed"),
+    }
+}
+
+/// Output per unit of input when swapping `size` units of the edge's input token.
+pub fn executable_price(edge: &PriceEdge, size: f64) -> Option<f64> {
+    let amount = from_units(size, edge.from_token.decimals);
+    let result = edge
+        .state
+        .get_amount_out(amount, &edge.from_token, &edge.to_token)
+        .ok()?;
+
+    Some(to_units(&result.amount, edge.to_token.decimals) / size)
+}
+
+struct RecordWriter {
+    directory: PathBuf,
+    format: RecordFormat,
+    rotation: LogRotation,
+    reference_sizes: HashMap<Symbol, Vec<f64>>,
+    /// Number of size/price column pairs, the most reference sizes configured for any asset
+    columns: usize,
+}
+
+impl RecordWriter {
+    fn run(self, mut receiver: mpsc::Receiver<EdgeUpdate>) {
+        let mut sink = match self.format {
+            RecordFormat::Csv => Sink::Csv(None),
+            RecordFormat::Parquet => Sink::Parquet(vec![]),
+        };
+        let mut period = String::new();
+
+        while let Some(update) = receiver.blocking_recv() {
+            let record = self.price_record(update);
+            let record_period = self.period(&record.timestamp);
+            if record_period != period {
+                if let Err(e) = self.rotate(&mut sink, &period) {
+                    tracing::error!("Failed to rotate price records: {}", e);
+                }
+                period = record_period;
+            }
+
+            if let Err(e) = self.write(&mut sink, &period, record) {
+                tracing::error!("Failed to write price record: {}", e);
+            }
+
+            // flush once the burst of updates from a block is written
+            let flushed = match &mut sink {
+                Sink::Csv(Some(writer)) if receiver.is_empty() => writer.flush(),
+                _ => Ok(()),
+            };
+            if let Err(e) = flushed {
+                tracing::error!("Failed to flush price records: {}", e);
+            }
+        }
+
+        if let Err(e) = self.rotate(&mut sink, &period) {
+            tracing::error!("Failed to close price records: {}", e);
+        }
+    }
+
+    /// Quotes the edge at the reference sizes of its input asset.
+    fn price_record(&self, update: EdgeUpdate) -> PriceRecord {
+        let EdgeUpdate { timestamp, block, edge, from_symbol, to_symbol } = update;
+
+        let quotes = self
+            .reference_sizes
+            .get(&from_symbol)
+            .map(|sizes| {
+                sizes.iter().map(|size| (*size, executable_price(&edge, *size))).collect()
+            })
+            .unwrap_or_default();
+
+        PriceRecord {
+            timestamp,
+            chain: chain_label(edge.chain),
+            block,
+            pool: edge.pool_address.clone(),
+            protocol: edge.protocol.to_str().to_string(),
+            from_symbol,
+            from_address: edge.from_token.address.to_string(),
+            to_symbol,
+            to_address: edge.to_token.address.to_string(),
+            spot_price: edge.price,
+            quotes,
+        }
+    }
+
+    fn period(&self, timestamp: &DateTime<Utc>) -> String {
+        match self.rotation {
+            LogRotation::Hourly => timestamp.format("-%Y-%m-%d-%H").to_string(),
+            LogRotation::Daily => timestamp.format("-%Y-%m-%d").to_string(),
+            LogRotation::Never => String::new(),
+        }
+    }
+
+    /// Closes the current CSV file, or writes out the rows buffered for Parquet.
+    fn rotate(&self, sink: &mut Sink, period: &str) -> Result<(), anyhow::Error> {
+        match sink {
+            Sink::Csv(writer) => {
+                if let Some(mut writer) = writer.take() {
+                    writer.flush()?;
+                }
+            }
+            Sink::Parquet(rows) => {
+                if !rows.is_empty() {
+                    self.write_parquet(period, rows)?;
+                    rows.clear();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write(
+        &self,
+        sink: &mut Sink,
+        period: &str,
+        record: PriceRecord,
+    ) -> Result<(), anyhow::Error> {
+        match sink {
+            Sink::Csv(writer) => {
+                if writer.is_none() {
+                    *writer = Some(self.open_csv(period)?);
+                }
+                writer.as_mut().unwrap().write_record(self.csv_row(&record))?;
+            }
+            Sink::Parquet(rows) => {
+                rows.push(record);
+                if rows.len() >= RECORDER_PARQUET_ROWS {
+                    self.write_parquet(period, rows)?;
+                    rows.clear();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn header(&self) -> Vec<String> {
+        let mut header = [
+            "timestamp_ms",
+            "chain",
+            "block",
+            "pool",
+            "protocol",
+            "from_symbol",
+            "from_address",
+            "to_symbol",
+            "to_address",
+            "spot_price",
+        ]
+        .map(String::from)
+        .to_vec();
+
+        for i in 1..=self.columns {
+            header.push(format!("size_{i}"));
+            header.push(format!("price_{i}"));
+        }
+
+        header
+    }
+
+    /// Appends to the period's file, writing the header only when the file is new.
+    fn open_csv(&self, period: &str) -> Result<csv::Writer<File>, anyhow::Error> {
+        let path = self.directory.join(format!("prices{period}.csv"));
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let is_new = file.metadata()?.len() == 0;
+
+        let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(file);
+        if is_new {
+            writer.write_record(self.header())?;
+        }
+
+        Ok(writer)
+    }
+
+    fn csv_row(&self, record: &PriceRecord) -> Vec<String> {
+        let mut row = vec![
+            record.timestamp.timestamp_millis().to_string(),
+            record.chain.clone(),
+            record.block.to_string(),
+            record.pool.clone(),
+            record.protocol.clone(),
+            record.from_symbol.clone(),
+            record.from_address.clone(),
+            record.to_symbol.clone(),
+            record.to_address.clone(),
+            record.spot_price.to_string(),
+        ];
+
+        for i in 0..self.columns {
+            let (size, price) = record.quotes.get(i).copied().unwrap_or((f64::NAN, None));
+            row.push(if size.is_nan() { String::new() } else { size.to_string() });
+            row.push(price.map(|p| p.to_string()).unwrap_or_default());
+        }
+
+        row
+    }
+
+    /// Parquet files cannot be appended to, so every batch of rows becomes its own file.
+    fn write_parquet(&self, period: &str, rows: &[PriceRecord]) -> Result<(), anyhow::Error> {
+        let path = next_part(&self.directory, &format!("prices{period}"), "parquet");
+        let batch = self.record_batch(rows)?;
+
+        let mut writer = ArrowWriter::try_new(File::create(&path)?, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        Ok(())
+    }
+
+    fn record_batch(&self, rows: &[PriceRecord]) -> Result<RecordBatch, anyhow::Error> {
+        let mut fields = vec![];
+        let mut columns: Vec<ArrayRef> = vec![];
+
+        let mut timestamps = UInt64Builder::new();
+        let mut blocks = UInt64Builder::new();
+        let mut spot_prices = Float64Builder::new();
+        let mut strings = (0..7).map(|_| StringBuilder::new()).collect::<Vec<_>>();
+        for row in rows {
+            timestamps.append_value(row.timestamp.timestamp_millis() as u64);
+            blocks.append_value(row.block);
+            spot_prices.append_value(row.spot_price);
+
+            let values = [
+                &row.chain,
+                &row.pool,
+                &row.protocol,
+                &row.from_symbol,
+                &row.from_address,
+                &row.to_symbol,
+                &row.to_address,
+            ];
+            for (builder, value) in strings.iter_mut().zip(values) {
+                builder.append_value(value);
+            }
+        }
+
+        let header = self.header();
+        let mut strings = strings.into_iter();
+        for name in &header[..10] {
+            let (data_type, column): (DataType, ArrayRef) = match name.as_str() {
+                "timestamp_ms" => (DataType::UInt64, Arc::new(timestamps.finish())),
+                "block" => (DataType::UInt64, Arc::new(blocks.finish())),
+                "spot_price" => (DataType::Float64, Arc::new(spot_prices.finish())),
+                _ => (DataType::Utf8, Arc::new(strings.next().unwrap().finish())),
+            };
+            fields.push(Field::new(name, data_type, false));
+            columns.push(column);
+        }
+
+        for (i, names) in header[10..].chunks(2).enumerate() {
+            let mut sizes = Float64Builder::new();
+            let mut prices = Float64Builder::new();
+            for row in rows {
+                let quote = row.quotes.get(i);
+                sizes.append_option(quote.map(|(size, _)| *size));
+                prices.append_option(quote.and_then(|(_, price)| *price));
+            }
+            fields.push(Field::new(&names[0], DataType::Float64, true));
+            columns.push(Arc::new(sizes.finish()));
+            fields.push(Field::new(&names[1], DataType::Float64, true));
+            columns.push(Arc::new(prices.finish()));
+        }
+
+        Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?)
+    }
+}
+
+enum Sink {
+    Csv(Option<csv::Writer<File>>),
+    Parquet(Vec<PriceRecord>),
+}
+
+/// First `{stem}-{n}.{extension}` that does not exist yet, so restarts never overwrite a file.
+fn next_part(directory: &Path, stem: &str, extension: &str) -> PathBuf {
+    (0..)
+        .map(|n| directory.join(format!("{stem}-{n:04}.{extension}")))
+        .find(|path| !path.exists())
+        .unwrap()
+}
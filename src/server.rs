@@ -0,0 +1,122 @@
+use crate::api::{get_edges, get_graph, get_pool, get_spreads, get_tokens};
+use crate::feed::{Feed, ws_handler};
+use crate::health::{healthz, readyz};
+use crate::metrics::metrics_handler;
+use crate::recorder::executable_price;
+use crate::types::{ArbitrageGraph, GraphSnapshot, Network, Symbol};
+use crate::utils::constants::API_SNAPSHOT_INTERVAL_MS;
+use axum::Router;
+use axum::routing::get;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::sync::{Mutex, watch};
+use tycho_common::models::Chain;
+
+#[derive(Clone)]
+pub struct ServerState {
+    /// Latest published copy of the graph, handlers never lock the live one
+    pub graph: watch::Receiver<Arc<GraphSnapshot>>,
+    pub feed: Feed,
+    /// Configured chains, checked by the health endpoints
+    pub networks: Vec<Network>,
+}
+
+/// What a published snapshot was taken from: the tokens and the last block of each chain.
+#[derive(PartialEq)]
+struct Version {
+    initialized: bool,
+    nodes: usize,
+    updates: HashMap<Chain, Instant>,
+}
+
+impl Version {
+    fn of(graph: &ArbitrageGraph) -> Self {
+        Self {
+            initialized: graph.initialized,
+            nodes: graph.graph.node_count(),
+            updates: graph
+                .chain_status
+                .iter()
+                .map(|(chain, status)| (*chain, status.updated_at))
+                .collect(),
+        }
+    }
+}
+
+/// Publishes a snapshot of the live graph at most every `API_SNAPSHOT_INTERVAL_MS`, and only
+/// once a chain applied a block or the tokens changed, so readers never lock the live graph.
+/// Edges are quoted at `reference_sizes`, which is empty unless the dashboard runs.
+pub async fn publish_snapshots(
+    arbitrage_graph: Arc<Mutex<ArbitrageGraph>>,
+    reference_sizes: HashMap<Symbol, f64>,
+    sender: watch::Sender<Arc<GraphSnapshot>>,
+) {
+    let mut interval = tokio::time::interval(Duration::from_millis(API_SNAPSHOT_INTERVAL_MS));
+    let mut published = None;
+
+    loop {
+        interval.tick().await;
+
+        let previous = Arc::clone(&sender.borrow());
+        let (mut snapshot, unquoted) = {
+            let graph = arbitrage_graph.lock().await;
+            let version = Some(Version::of(&graph));
+            if version == published {
+                continue;
+            }
+            published = version;
+            graph.snapshot(&previous, &reference_sizes)
+        };
+
+        // simulating the moved pools runs off the graph lock and the async workers
+        if !unquoted.is_empty() {
+            let quoted = tokio::task::spawn_blocking(move || {
+                unquoted
+                    .into_iter()
+                    .map(|(idx, edge, size)| (idx, executable_price(&edge, size)))
+                    .collect::<Vec<_>>()
+            })
+            .await;
+
+            for (idx, price) in quoted.unwrap_or_default() {
+                if let Some(edge) = snapshot.graph.edge_weight_mut(idx) {
+                    edge.executable_price = price;
+                }
+            }
+        }
+
+        if sender.send(Arc::new(snapshot)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Serves the HTTP endpoints until the listener fails.
+pub async fn run_server(listen: String, state: ServerState) {
+    let app = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(metrics_handler))
+        .route("/tokens", get(get_tokens))
+        .route("/edges", get(get_edges))
+        .route("/pools/{address}", get(get_pool))
+        .route("/spreads/{symbol}", get(get_spreads))
+        .route("/graph", get(get_graph))
+        .route("/ws", get(ws_handler))
+        .with_state(state);
+
+    let listener = match TcpListener::bind(&listen).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Failed to bind HTTP server to {}: {}", listen, e);
+            return;
+        }
+    };
+
+    tracing::info!("HTTP server listening on {}", listen);
+    if let Err(e) = axum::serve(listener, app).await {
+        tracing::error!("HTTP server stopped: {}", e);
+    }
+}
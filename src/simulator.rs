@@ -0,0 +1,102 @@
+use crate::alerts::alert;
+use crate::metrics::opportunity_rejected;
+use crate::types::{AlertEvent, EncodedLeg, Network, OpportunityLeg, SimulationResult};
+use crate::utils::rpc::{create_provider, create_signer};
+use alloy::primitives::{Address, U256};
+use alloy::providers::{DynProvider, Provider};
+use alloy::rpc::types::TransactionRequest;
+use num_bigint::BigUint;
+use std::collections::HashMap;
+use tycho_common::models::Chain;
+
+/// Replays encoded legs with `eth_call` against each chain's RPC, typically a local Anvil fork.
+pub struct ForkSimulator {
+    providers: HashMap<Chain, DynProvider>,
+    sender: Address,
+}
+
+impl ForkSimulator {
+    pub fn new(networks: &[Network], private_key: &str) -> Result<Self, anyhow::Error> {
+        let signer = create_signer(private_key)?;
+
+        let mut providers = HashMap::new();
+
+        for network in networks {
+            providers.insert(network.chain, create_provider(network)?);
+        }
+
+        Ok(Self {
+            providers,
+            sender: signer.address(),
+        })
+    }
+
+    fn provider(&self, chain: Chain) -> Result<&DynProvider, anyhow::Error> {
+        self.providers
+            .get(&chain)
+            .ok_or_else(|| anyhow::anyhow!("No provider for chain {:?}", chain))
+    }
+
+    fn request(&self, encoded: &EncodedLeg) -> TransactionRequest {
+        TransactionRequest::default()
+            .from(self.sender)
+            .to(Address::from_slice(&encoded.to))
+            .value(U256::from_be_slice(&encoded.value.to_bytes_be()))
+            .input(encoded.data.clone().into())
+    }
+
+    /// Replays a call that checks its own outcome, e.g. a flash executor reverting unless the
+    /// loan is repaid with profit. Returns the gas it uses, a revert is an error.
+    pub async fn simulate_call(&self, encoded: &EncodedLeg) -> Result<u64, anyhow::Error> {
+        let provider = self.provider(encoded.chain)?;
+        let tx = self.request(encoded);
+
+        provider.call(tx.clone()).await?;
+        Ok(provider.estimate_gas(tx).await?)
+    }
+
+    /// Accepts the leg when the replayed output reaches the leg's slippage-bounded minimum, the
+    /// same `checked_amount` the router enforces on-chain.
+    pub async fn simulate_leg(
+        &self,
+        leg: &OpportunityLeg,
+        encoded: &EncodedLeg,
+    ) -> Result<SimulationResult, anyhow::Error> {
+        let provider = self.provider(leg.chain)?;
+        let tx = self.request(encoded);
+
+        let output = provider.call(tx.clone()).await?;
+        if output.len() < 32 {
+            return Err(anyhow::anyhow!("Unexpected router output: {}", output));
+        }
+        let gas_used = provider.estimate_gas(tx).await?;
+
+        let expected_amount_out = leg.amount_out();
+        let simulated_amount_out = BigUint::from_bytes_be(&output[..32]);
+        let min_amount_out = leg.min_amount_out.clone();
+        let accepted = simulated_amount_out >= min_amount_out;
+
+        if !accepted {
+            opportunity_rejected("simulation");
+            let message = format!(
+                "Simulated {} below minimum {} (expected {})",
+                simulated_amount_out, min_amount_out, expected_amount_out
+            );
+            tracing::warn!(chain = ?leg.chain, "Rejecting leg: {}", message);
+            alert(AlertEvent::RiskLimit {
+                limit: "min_amount_out".into(),
+                subject: format!("{:?}", leg.chain),
+                message,
+            });
+        }
+
+        Ok(SimulationResult {
+            chain: leg.chain,
+            expected_amount_out,
+            simulated_amount_out,
+            min_amount_out,
+            gas_used,
+            accepted,
+        })
+    }
+}
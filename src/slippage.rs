@@ -0,0 +1,340 @@
+use crate::alerts::{alert, alert_opportunity};
+use crate::latency::observe_stage;
+use crate::metrics::{opportunity_detected, opportunity_rejected};
+use crate::types::{
+    AlertEvent, ArbitrageGraph, Network, Opportunity, OpportunityLeg, PegType, Symbol,
+};
+use crate::utils::constants::{
+    SLIPPAGE_BASE_BPS, SLIPPAGE_CONFIDENCE, SLIPPAGE_MAX_BPS, SOFT_PEG_HAIRCUT_BPS,
+};
+use crate::utils::units::{sub_bps, to_units};
+use std::collections::{HashMap, HashSet};
+
+/// `SLIPPAGE_CONFIDENCE` standard deviations of the price move expected over one block,
+/// accumulated over the leg's pools. Pools without enough history add nothing.
+pub fn leg_volatility_bps(
+    graph: &ArbitrageGraph,
+    leg: &OpportunityLeg,
+    block_time_ms: u64,
+) -> u64 {
+    let block_time_secs = block_time_ms as f64 / 1000.0;
+
+    let variance: f64 = leg
+        .hops
+        .iter()
+        .filter_map(|hop| graph.edge_index(&hop.pool_address, &hop.from_token.address))
+        .filter_map(|idx| graph.price_variance_per_sec(idx))
+        .map(|variance_per_sec| variance_per_sec * block_time_secs)
+        .sum();
+
+    (SLIPPAGE_CONFIDENCE * variance.sqrt() * 10_000.0).ceil() as u64
+}
+
+/// Tolerance for one leg: a fixed base plus the leg's volatility term.
+pub fn leg_tolerance_bps(volatility_bps: u64) -> u64 {
+    (SLIPPAGE_BASE_BPS + volatility_bps).min(SLIPPAGE_MAX_BPS)
+}
+
+/// Sets the tolerance and minimum output of every leg, then checks that no leg's volatility
+/// term exceeds `risk_param` basis points and that the opportunity still does not lose any
+/// asset when every leg only returns its minimum. The fixed base is not counted against
+/// `risk_param`.
+pub fn apply_slippage_bounds(
+    graph: &ArbitrageGraph,
+    networks: &[Network],
+    risk_param: u64,
+    opportunity: &mut Opportunity,
+) -> Result<(), String> {
+    opportunity_detected();
+
+    for leg in opportunity.legs.iter_mut() {
+        let network = networks
+            .iter()
+            .find(|n| n.chain == leg.chain)
+            .ok_or_else(|| format!("no network for {:?}", leg.chain))?;
+
+        let volatility_bps = leg_volatility_bps(graph, leg, network.block_time_ms);
+        leg.tolerance_bps = leg_tolerance_bps(volatility_bps);
+        leg.min_amount_out = sub_bps(&leg.amount_out(), leg.tolerance_bps);
+
+        if volatility_bps > risk_param {
+            opportunity_rejected("risk_param");
+            let message = format!(
+                "{} needs {} bps of volatility tolerance on {:?}, risk param allows {}",
+                opportunity.id, volatility_bps, leg.chain, risk_param
+            );
+            alert(AlertEvent::RiskLimit {
+                limit: "risk_param".into(),
+                subject: format!("{:?}", leg.chain),
+                message: message.clone(),
+            });
+            return Err(message);
+        }
+    }
+
+    let flows = worst_case_flows(graph, opportunity);
+
+    if let Some((symbol, flow)) = flows.iter().find(|(_, flow)| **flow < 0.0) {
+        opportunity_rejected("slippage");
+        return Err(format!(
+            "{} loses {:.6} {} at minimum outputs",
+            opportunity.id, -flow, symbol
+        ));
+    }
+
+    if !flows.values().any(|flow| *flow > 0.0) {
+        opportunity_rejected("slippage");
+        return Err(format!("{} has no profit left at minimum outputs", opportunity.id));
+    }
+
+    observe_stage(&opportunity.timing, "slippage_bounds");
+    alert_opportunity(opportunity, &flows);
+
+    Ok(())
+}
+
+/// Net amount of each asset, in token units, when every leg only returns its minimum. A
+/// soft-pegged token received where the opportunity spends another token of the asset only
+/// counts `SOFT_PEG_HAIRCUT_BPS` below par, the two need not trade one for one.
+fn worst_case_flows(graph: &ArbitrageGraph, opportunity: &Opportunity) -> HashMap<Symbol, f64> {
+    let mut flows: HashMap<Symbol, f64> = HashMap::new();
+
+    let spent_tokens = opportunity
+        .legs
+        .iter()
+        .filter_map(|leg| Some((leg.chain, &leg.hops.first()?.from_token.address)))
+        .collect::<HashSet<_>>();
+
+    for leg in &opportunity.legs {
+        let (Some(first), Some(last)) = (leg.hops.first(), leg.hops.last()) else {
+            continue;
+        };
+
+        let (spent, _) = graph.asset_for(leg.chain, &first.from_token);
+        let (received, peg) = graph.asset_for(leg.chain, &last.to_token);
+
+        let mut amount_out = to_units(&leg.min_amount_out, last.to_token.decimals);
+        if peg == PegType::Soft && !spent_tokens.contains(&(leg.chain, &last.to_token.address)) {
+            amount_out *= 1.0 - SOFT_PEG_HAIRCUT_BPS as f64 / 10_000.0;
+        }
+
+        *flows.entry(spent).or_default() -=
+            to_units(&leg.amount_in(), first.from_token.decimals);
+        *flows.entry(received).or_default() += amount_out;
+    }
+
+    flows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AssetGroup, BlockTiming, Protocol, SwapHop};
+    use crate::utils::constants::network;
+    use crate::utils::units::from_units;
+    use alloy::primitives::U256;
+    use num_bigint::BigUint;
+    use petgraph::prelude::EdgeIndex;
+    use std::collections::VecDeque;
+    use std::str::FromStr;
+    use std::time::{Duration, Instant, SystemTime};
+    use tycho_common::Bytes;
+    use tycho_common::models::Chain;
+    use tycho_simulation::evm::protocol::uniswap_v2::state::UniswapV2State;
+    use tycho_simulation::models::Token;
+    use tycho_simulation::protocol::models::ProtocolComponent;
+
+    // risk_param of config.example.yml
+    const EXAMPLE_RISK_PARAM: u64 = 5;
+
+    fn token(address: &str, symbol: &str, decimals: usize) -> Token {
+        Token {
+            address: Bytes::from_str(address).unwrap(),
+            decimals,
+            symbol: symbol.to_string(),
+            gas: BigUint::default(),
+        }
+    }
+
+    /// A WETH/USDC pool on Ethereum whose WETH price moved by `step` every second for a
+    /// minute, and a cycle through it that returns 4% at expected outputs.
+    fn opportunity_on_pool(step: f64) -> (ArbitrageGraph, Opportunity) {
+        let weth = token("0x0000000000000000000000000000000000000001", "WETH", 18);
+        let usdc = token("0x0000000000000000000000000000000000000002", "USDC", 6);
+
+        let mut graph = ArbitrageGraph::new(vec![]);
+        graph.add_token(Chain::Ethereum, weth.clone());
+        graph.add_token(Chain::Ethereum, usdc.clone());
+
+        let component = ProtocolComponent::new(
+            Bytes::from_str("0xa1").unwrap(),
+            "uniswap_v2".to_string(),
+            "uniswap_v2_pool".to_string(),
+            Chain::Ethereum,
+            vec![weth.clone(), usdc.clone()],
+            vec![],
+            HashMap::new(),
+            Bytes::default(),
+            Default::default(),
+        );
+        let state = UniswapV2State::new(
+            U256::from(1_000u128 * 10u128.pow(18)),
+            U256::from(2_000_000u128 * 10u128.pow(6)),
+        );
+        graph.handle_new_pair(component.clone(), Box::new(state));
+
+        let start = Instant::now();
+        let history = (0..60)
+            .map(|i| (start + Duration::from_secs(i), 2000.0 * (1.0 + step).powi(i as i32)))
+            .collect::<VecDeque<_>>();
+        for idx in graph.graph.edge_indices().collect::<Vec<EdgeIndex>>() {
+            graph.price_history.insert(idx, history.clone());
+        }
+
+        let hop = |from: &Token, to: &Token, amount_in: u128, amount_out: u128| SwapHop {
+            pool_address: component.id.to_string(),
+            protocol: Protocol::UniswapV2,
+            component: component.clone(),
+            from_token: from.clone(),
+            to_token: to.clone(),
+            amount_in: BigUint::from(amount_in),
+            amount_out: BigUint::from(amount_out),
+        };
+        let leg = OpportunityLeg {
+            chain: Chain::Ethereum,
+            hops: vec![
+                hop(&weth, &usdc, 10u128.pow(18), 2_080 * 10u128.pow(6)),
+                hop(&usdc, &weth, 2_080 * 10u128.pow(6), 104 * 10u128.pow(16)),
+            ],
+            tolerance_bps: 0,
+            min_amount_out: BigUint::default(),
+        };
+        let opportunity = Opportunity {
+            id: "test".to_string(),
+            legs: vec![leg],
+            timing: BlockTiming {
+                chain: Chain::Ethereum,
+                block: 1,
+                received_at: Instant::now(),
+                received_at_system: SystemTime::now(),
+            },
+            flash: None,
+        };
+
+        (graph, opportunity)
+    }
+
+    #[test]
+    fn calm_pool_passes_example_risk_param() {
+        let (graph, mut opportunity) = opportunity_on_pool(0.000_01);
+        let networks = vec![network("ethereum".to_string()).unwrap()];
+
+        let result = apply_slippage_bounds(&graph, &networks, EXAMPLE_RISK_PARAM, &mut opportunity);
+
+        assert_eq!(result, Ok(()));
+        let leg = &opportunity.legs[0];
+        assert!(leg.tolerance_bps > SLIPPAGE_BASE_BPS);
+        assert!(leg.min_amount_out < leg.amount_out());
+    }
+
+    #[test]
+    fn volatile_pool_exceeds_example_risk_param() {
+        let (graph, mut opportunity) = opportunity_on_pool(0.001);
+        let networks = vec![network("ethereum".to_string()).unwrap()];
+
+        let result = apply_slippage_bounds(&graph, &networks, EXAMPLE_RISK_PARAM, &mut opportunity);
+
+        assert!(result.is_err_and(|reason| reason.contains("volatility tolerance")));
+    }
+
+    /// One-hop leg on its own pool, returning `min_out` units of `to` at minimum.
+    fn leg(chain: Chain, from: &Token, to: &Token, amount_in: f64, min_out: f64) -> OpportunityLeg {
+        let component = ProtocolComponent::new(
+            Bytes::from_str("0xb1").unwrap(),
+            "uniswap_v2".to_string(),
+            "uniswap_v2_pool".to_string(),
+            chain,
+            vec![from.clone(), to.clone()],
+            vec![],
+            HashMap::new(),
+            Bytes::default(),
+            Default::default(),
+        );
+        let hop = SwapHop {
+            pool_address: component.id.to_string(),
+            protocol: Protocol::UniswapV2,
+            component,
+            from_token: from.clone(),
+            to_token: to.clone(),
+            amount_in: from_units(amount_in, from.decimals),
+            amount_out: from_units(min_out, to.decimals),
+        };
+
+        OpportunityLeg {
+            chain,
+            hops: vec![hop],
+            tolerance_bps: 0,
+            min_amount_out: from_units(min_out, to.decimals),
+        }
+    }
+
+    /// WBTC sold on Ethereum and cbBTC bought back on Unichain, both in the soft-pegged BTC
+    /// group, returning `btc_out` for one BTC.
+    fn soft_peg_crossing(btc_out: f64) -> (ArbitrageGraph, Opportunity) {
+        let wbtc = token("0x0000000000000000000000000000000000000001", "WBTC", 8);
+        let cbbtc = token("0x0000000000000000000000000000000000000003", "cbBTC", 8);
+        let eth_usdc = token("0x0000000000000000000000000000000000000002", "USDC", 6);
+        let uni_usdc = token("0x0000000000000000000000000000000000000004", "USDC", 6);
+
+        let mut graph = ArbitrageGraph::new(vec![AssetGroup {
+            name: "BTC".to_string(),
+            peg: PegType::Soft,
+            members: HashSet::from([
+                (Chain::Ethereum, wbtc.address.clone()),
+                (Chain::Unichain, cbbtc.address.clone()),
+            ]),
+        }]);
+        graph.add_token(Chain::Ethereum, wbtc.clone());
+        graph.add_token(Chain::Ethereum, eth_usdc.clone());
+        graph.add_token(Chain::Unichain, cbbtc.clone());
+        graph.add_token(Chain::Unichain, uni_usdc.clone());
+
+        let opportunity = Opportunity {
+            id: "crossing".to_string(),
+            legs: vec![
+                leg(Chain::Ethereum, &wbtc, &eth_usdc, 1.0, 100_000.0),
+                leg(Chain::Unichain, &uni_usdc, &cbbtc, 100_000.0, btc_out),
+            ],
+            timing: BlockTiming {
+                chain: Chain::Ethereum,
+                block: 1,
+                received_at: Instant::now(),
+                received_at_system: SystemTime::now(),
+            },
+            flash: None,
+        };
+
+        (graph, opportunity)
+    }
+
+    #[test]
+    fn soft_peg_crossing_is_haircut() {
+        let (graph, opportunity) = soft_peg_crossing(1.003);
+        let flows = worst_case_flows(&graph, &opportunity);
+        assert!(flows["BTC"] < 0.0);
+
+        let (graph, opportunity) = soft_peg_crossing(1.01);
+        let flows = worst_case_flows(&graph, &opportunity);
+        assert!(flows["BTC"] > 0.0);
+    }
+
+    #[test]
+    fn same_token_return_is_not_haircut() {
+        let (graph, mut opportunity) = opportunity_on_pool(0.0);
+        opportunity.legs[0].min_amount_out = opportunity.legs[0].amount_out();
+
+        let flows = worst_case_flows(&graph, &opportunity);
+
+        assert!((flows["WETH"] - 0.04).abs() < 1e-9);
+    }
+}
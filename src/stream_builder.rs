@@ -2,6 +2,7 @@ use crate::types::{Network, Protocol};
 use std::collections::HashMap;
 use tycho_client::feed::component_tracker::ComponentFilter;
 use tycho_simulation::evm::{
+    decoder::DecoderContext,
     engine_db::tycho_db::PreCachedDB,
     protocol::{
         ekubo::state::EkuboState,
@@ -17,12 +18,15 @@ use tycho_simulation::evm::{
 use tycho_simulation::models::Token;
 use tycho_simulation::tycho_core::Bytes;
 
+type VmState = EVMPoolState<PreCachedDB>;
+
+#[tracing::instrument(skip_all, fields(chain = %network.name))]
 pub async fn create_protocol_stream_builder(
     network: Network,
-    rpc_url: String,
     tvl_filter: ComponentFilter,
     api_key: String,
     tokens: HashMap<Bytes, Token>,
+    protocols: Vec<Protocol>,
 ) -> ProtocolStreamBuilder {
     tracing::info!(
         "Creating protocol stream builder for chain {}",
@@ -30,85 +34,129 @@ pub async fn create_protocol_stream_builder(
     );
 
     let mut builder = ProtocolStreamBuilder::new(network.tycho_url.as_str(), network.chain);
-    builder = add_exchanges(builder, &network.chain, tvl_filter, rpc_url);
+    builder = add_exchanges(builder, &network.chain, tvl_filter, &network.rpc, &protocols);
     builder = setup_stream_builder(builder, api_key, tokens).await;
 
     builder
 }
 
+pub fn supported_protocols(chain: &Chain) -> Vec<Protocol> {
+    match chain {
+        Chain::Ethereum => vec![
+            Protocol::UniswapV2,
+            Protocol::UniswapV3,
+            Protocol::UniswapV4,
+            Protocol::EkuboV2,
+            Protocol::SushiswapV2,
+            Protocol::PancakeswapV2,
+            Protocol::PancakeswapV3,
+            Protocol::VmBalancerV2,
+            Protocol::VmCurve,
+        ],
+        Chain::Base | Chain::Unichain => vec![
+            Protocol::UniswapV2,
+            Protocol::UniswapV3,
+            Protocol::UniswapV4,
+        ],
+        _ => vec![],
+    }
+}
+
+/// One exchange to register on a chain's stream. VM exchanges carry the chain's RPC URL, their
+/// engine loads contract code that Tycho does not index from it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExchangeSpec {
+    pub protocol: Protocol,
+    pub rpc_url: Option<String>,
+}
+
+/// The supported exchanges of `chain`, restricted to `protocols` when non-empty. VM exchanges
+/// are skipped without an RPC URL.
+pub fn exchange_specs(chain: &Chain, rpc_url: &str, protocols: &[Protocol]) -> Vec<ExchangeSpec> {
+    let mut specs = vec![];
+
+    for protocol in supported_protocols(chain) {
+        if !protocols.is_empty() && !protocols.contains(&protocol) {
+            continue;
+        }
+
+        if !protocol.is_vm() {
+            specs.push(ExchangeSpec {
+                protocol,
+                rpc_url: None,
+            });
+        } else if rpc_url.is_empty() {
+            tracing::warn!("No RPC URL for {:?}, skipping {}", chain, protocol.to_str());
+        } else {
+            specs.push(ExchangeSpec {
+                protocol,
+                rpc_url: Some(rpc_url.to_string()),
+            });
+        }
+    }
+
+    specs
+}
+
 pub fn add_exchanges(
     mut builder: ProtocolStreamBuilder,
     chain: &Chain,
     tvl_filter: ComponentFilter,
-    rpc_url: String,
+    rpc_url: &str,
+    protocols: &[Protocol],
 ) -> ProtocolStreamBuilder {
-    match chain {
-        Chain::Ethereum => {
-            builder = builder
-                .exchange::<UniswapV2State>(Protocol::UniswapV2.to_str(), tvl_filter.clone(), None)
-                .exchange::<UniswapV3State>(Protocol::UniswapV3.to_str(), tvl_filter.clone(), None)
-                .exchange::<UniswapV4State>(
-                    Protocol::UniswapV4.to_str(),
-                    tvl_filter.clone(),
-                    Some(uniswap_v4_pool_with_hook_filter),
-                )
-                .exchange::<EkuboState>(Protocol::EkuboV2.to_str(), tvl_filter.clone(), None)
-                .exchange::<UniswapV2State>(
-                    Protocol::SushiswapV2.to_str(),
-                    tvl_filter.clone(),
-                    None,
-                )
-                .exchange::<UniswapV2State>(
-                    Protocol::PancakeswapV2.to_str(),
-                    tvl_filter.clone(),
-                    None,
-                )
-                .exchange::<UniswapV3State>(
-                    Protocol::PancakeswapV3.to_str(),
-                    tvl_filter.clone(),
-                    None,
-                );
-
-            if !rpc_url.is_empty() {
-                builder = builder
-                    .exchange::<EVMPoolState<PreCachedDB>>(
-                        Protocol::VmBalancerV2.to_str(),
-                        tvl_filter.clone(),
-                        Some(balancer_v2_pool_filter),
-                    )
-                    .exchange::<EVMPoolState<PreCachedDB>>(
-                        Protocol::VmCurve.to_str(),
-                        tvl_filter.clone(),
-                        Some(curve_pool_filter),
-                    )
-            }
-        }
-        Chain::Base => {
-            builder = builder
-                .exchange::<UniswapV2State>(Protocol::UniswapV2.to_str(), tvl_filter.clone(), None)
-                .exchange::<UniswapV3State>(Protocol::UniswapV3.to_str(), tvl_filter.clone(), None)
-                .exchange::<UniswapV4State>(
-                    Protocol::UniswapV4.to_str(),
-                    tvl_filter.clone(),
-                    Some(uniswap_v4_pool_with_hook_filter),
-                );
-        }
-        Chain::Unichain => {
-            builder = builder
-                .exchange::<UniswapV2State>(Protocol::UniswapV2.to_str(), tvl_filter.clone(), None)
-                .exchange::<UniswapV3State>(Protocol::UniswapV3.to_str(), tvl_filter.clone(), None)
-                .exchange::<UniswapV4State>(
-                    Protocol::UniswapV4.to_str(),
-                    tvl_filter.clone(),
-                    Some(uniswap_v4_pool_with_hook_filter),
-                );
-        }
-        _ => {}
-    };
+    for spec in exchange_specs(chain, rpc_url, protocols) {
+        builder = add_exchange(builder, spec, tvl_filter.clone());
+    }
 
     builder
 }
 
+/// Decoder context of a VM exchange, bound to its own chain's RPC instead of a process-wide one.
+fn vm_context(rpc_url: Option<String>) -> DecoderContext {
+    let mut context = DecoderContext::new();
+    if let Some(rpc_url) = rpc_url {
+        context = context.vm_rpc_url(rpc_url);
+    }
+
+    context
+}
+
+fn add_exchange(
+    builder: ProtocolStreamBuilder,
+    spec: ExchangeSpec,
+    tvl_filter: ComponentFilter,
+) -> ProtocolStreamBuilder {
+    let protocol = spec.protocol;
+
+    match protocol {
+        Protocol::UniswapV2 | Protocol::SushiswapV2 | Protocol::PancakeswapV2 => {
+            builder.exchange::<UniswapV2State>(protocol.to_str(), tvl_filter, None)
+        }
+        Protocol::UniswapV3 | Protocol::PancakeswapV3 => {
+            builder.exchange::<UniswapV3State>(protocol.to_str(), tvl_filter, None)
+        }
+        Protocol::UniswapV4 => builder.exchange::<UniswapV4State>(
+            protocol.to_str(),
+            tvl_filter,
+            Some(uniswap_v4_pool_with_hook_filter),
+        ),
+        Protocol::EkuboV2 => builder.exchange::<EkuboState>(protocol.to_str(), tvl_filter, None),
+        Protocol::VmBalancerV2 => builder.exchange_with_decoder_context::<VmState>(
+            protocol.to_str(),
+            tvl_filter,
+            Some(balancer_v2_pool_filter),
+            vm_context(spec.rpc_url),
+        ),
+        Protocol::VmCurve => builder.exchange_with_decoder_context::<VmState>(
+            protocol.to_str(),
+            tvl_filter,
+            Some(curve_pool_filter),
+            vm_context(spec.rpc_url),
+        ),
+    }
+}
+
 pub async fn setup_stream_builder(
     mut builder: ProtocolStreamBuilder,
     api_key: String,
@@ -122,3 +170,65 @@ pub async fn setup_stream_builder(
 
     builder
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vm_exchanges_get_their_own_chain_rpc_url() {
+        let ethereum = exchange_specs(&Chain::Ethereum, "https://ethereum.example", &[]);
+        let base = exchange_specs(&Chain::Base, "https://base.example", &[]);
+
+        let vm_urls = |specs: &[ExchangeSpec]| {
+            specs
+                .iter()
+                .filter(|spec| spec.protocol.is_vm())
+                .map(|spec| spec.rpc_url.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vm_urls(&ethereum),
+            vec![Some("https://ethereum.example".to_string()); 2]
+        );
+        assert!(vm_urls(&base).is_empty());
+        assert!(
+            base.iter()
+                .chain(&ethereum)
+                .filter(|spec| !spec.protocol.is_vm())
+                .all(|spec| spec.rpc_url.is_none())
+        );
+    }
+
+    #[test]
+    fn vm_exchanges_are_skipped_without_rpc_url() {
+        let specs = exchange_specs(&Chain::Ethereum, "", &[]);
+
+        assert!(!specs.is_empty());
+        assert!(specs.iter().all(|spec| !spec.protocol.is_vm()));
+    }
+
+    #[test]
+    fn exchanges_are_restricted_to_configured_protocols() {
+        let specs = exchange_specs(
+            &Chain::Ethereum,
+            "https://ethereum.example",
+            &[Protocol::UniswapV3, Protocol::VmCurve],
+        );
+
+        assert_eq!(
+            specs,
+            vec![
+                ExchangeSpec {
+                    protocol: Protocol::UniswapV3,
+                    rpc_url: None,
+                },
+                ExchangeSpec {
+                    protocol: Protocol::VmCurve,
+                    rpc_url: Some("https://ethereum.example".to_string()),
+                },
+            ]
+        );
+    }
+}
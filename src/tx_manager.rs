@@ -0,0 +1,346 @@
+use crate::types::{EncodedLeg, Network, TxEvent};
+use crate::utils::abi::IERC20;
+use crate::utils::constants::{
+    TX_FEE_BUMP_PERCENT, TX_GAS_LIMIT_BUFFER_PERCENT, TX_MAX_REPLACEMENTS,
+    TX_MIN_POLL_INTERVAL_MS, TX_STUCK_AFTER_BLOCKS,
+};
+use crate::utils::rpc::{create_provider, create_signer};
+use alloy::network::{EthereumWallet, TransactionBuilder};
+use alloy::primitives::{Address, TxHash, U256};
+use alloy::providers::{DynProvider, Provider};
+use alloy::rpc::types::{TransactionReceipt, TransactionRequest};
+use std::time::Duration;
+use tokio::sync::{Mutex, broadcast};
+
+#[derive(Debug, Clone, Copy)]
+struct Fees {
+    max_fee_per_gas: u128,
+    max_priority_fee_per_gas: u128,
+}
+
+impl Fees {
+    fn bumped(&self) -> Self {
+        Self {
+            max_fee_per_gas: self.max_fee_per_gas * (100 + TX_FEE_BUMP_PERCENT) / 100,
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas
+                * (100 + TX_FEE_BUMP_PERCENT)
+                / 100,
+        }
+    }
+
+    fn max(self, other: Self) -> Self {
+        Self {
+            max_fee_per_gas: self.max_fee_per_gas.max(other.max_fee_per_gas),
+            max_priority_fee_per_gas: self
+                .max_priority_fee_per_gas
+                .max(other.max_priority_fee_per_gas),
+        }
+    }
+}
+
+/// Sends and follows transactions on one chain. Owns the signer and the local nonce, so all
+/// transactions of a chain must go through the same manager.
+pub struct TxManager {
+    network: Network,
+    provider: DynProvider,
+    wallet: EthereumWallet,
+    address: Address,
+    nonce: Mutex<Option<u64>>,
+    events: broadcast::Sender<TxEvent>,
+}
+
+impl TxManager {
+    pub fn new(network: &Network, private_key: &str) -> Result<Self, anyhow::Error> {
+        let signer = create_signer(private_key)?;
+        let (events, _) = broadcast::channel(256);
+
+        Ok(Self {
+            network: network.clone(),
+            provider: create_provider(network)?,
+            address: signer.address(),
+            wallet: EthereumWallet::from(signer),
+            nonce: Mutex::new(None),
+            events,
+        })
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TxEvent> {
+        self.events.subscribe()
+    }
+
+    /// Polling interval and stuck timeout both scale with the chain's block time.
+    fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.network.block_time_ms.max(TX_MIN_POLL_INTERVAL_MS))
+    }
+
+    async fn next_nonce(&self) -> Result<u64, anyhow::Error> {
+        let mut nonce = self.nonce.lock().await;
+
+        let next = match *nonce {
+            Some(next) => next,
+            None => self.provider.get_transaction_count(self.address).pending().await?,
+        };
+        *nonce = Some(next + 1);
+
+        Ok(next)
+    }
+
+    /// Forgets the local nonce so the next transaction resyncs it from the node.
+    async fn reset_nonce(&self) {
+        *self.nonce.lock().await = None;
+    }
+
+    async fn estimate_fees(&self) -> Result<Fees, anyhow::Error> {
+        let estimation = self.provider.estimate_eip1559_fees().await?;
+
+        Ok(Fees {
+            max_fee_per_gas: estimation.max_fee_per_gas,
+            max_priority_fee_per_gas: estimation.max_priority_fee_per_gas,
+        })
+    }
+
+    async fn send(
+        &self,
+        tx: TransactionRequest,
+        nonce: u64,
+        fees: Fees,
+    ) -> Result<TxHash, anyhow::Error> {
+        let envelope = tx
+            .with_nonce(nonce)
+            .with_max_fee_per_gas(fees.max_fee_per_gas)
+            .with_max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+            .build(&self.wallet)
+            .await?;
+
+        let pending = self.provider.send_tx_envelope(envelope).await?;
+
+        Ok(*pending.tx_hash())
+    }
+
+    fn emit(&self, event: TxEvent) {
+        tracing::info!("Transaction event: {:?}", event);
+        // no subscribers is fine
+        let _ = self.events.send(event);
+    }
+
+    pub async fn execute(&self, id: &str, leg: &EncodedLeg) -> Result<TxEvent, anyhow::Error> {
+        let tx = TransactionRequest::default()
+            .with_to(Address::from_slice(&leg.to))
+            .with_value(U256::from_be_slice(&leg.value.to_bytes_be()))
+            .with_input(leg.data.clone());
+
+        self.execute_request(id, tx).await
+    }
+
+    /// Submits `tx` and follows it until it is included, reverted or dropped. Transactions
+    /// pending for `TX_STUCK_AFTER_BLOCKS` blocks are re-sent with bumped fees, then cancelled
+    /// with a self-transfer. Failed polls are retried, so the nonce always resolves to an event.
+    pub async fn execute_request(
+        &self,
+        id: &str,
+        tx: TransactionRequest,
+    ) -> Result<TxEvent, anyhow::Error> {
+        let chain = self.network.chain;
+
+        let tx = tx
+            .with_from(self.address)
+            .with_chain_id(self.network.chainid);
+        let gas_limit = self.provider.estimate_gas(tx.clone()).await?;
+        let tx = tx.with_gas_limit(gas_limit * (100 + TX_GAS_LIMIT_BUFFER_PERCENT) / 100);
+
+        // estimated before reserving the nonce, an early return must not leave a gap
+        let mut fees = self.estimate_fees().await?;
+        let nonce = self.next_nonce().await?;
+
+        let hash = match self.send(tx.clone(), nonce, fees).await {
+            Ok(hash) => hash,
+            Err(e) => {
+                self.reset_nonce().await;
+                return Err(e);
+            }
+        };
+        self.emit(TxEvent::Submitted {
+            chain,
+            id: id.to_string(),
+            hash,
+            nonce,
+        });
+
+        let mut hashes = vec![hash];
+        let mut cancel_hashes = vec![];
+        let mut replacements = 0;
+        let mut sent_at_block = None;
+
+        loop {
+            tokio::time::sleep(self.poll_interval()).await;
+
+            match self.nonce_outcome(id, nonce, &hashes, &cancel_hashes).await {
+                Ok(Some(event)) => {
+                    self.emit(event.clone());
+                    return Ok(event);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!("Polling {} on {:?} failed, retrying: {}", id, chain, e);
+                    continue;
+                }
+            }
+
+            let block = match self.provider.get_block_number().await {
+                Ok(block) => block,
+                Err(e) => {
+                    tracing::warn!("Fetching the block on {:?} failed, retrying: {}", chain, e);
+                    continue;
+                }
+            };
+            let sent_at = *sent_at_block.get_or_insert(block);
+            if block < sent_at + TX_STUCK_AFTER_BLOCKS {
+                continue;
+            }
+
+            let estimated = match self.estimate_fees().await {
+                Ok(estimated) => estimated,
+                Err(e) => {
+                    tracing::warn!("Fee estimation on {:?} failed, bumping only: {}", chain, e);
+                    fees
+                }
+            };
+            fees = estimated.max(fees.bumped());
+
+            // once cancelling, only the cancel is re-sent; the sent transactions stay polled
+            // until one of them is mined or the nonce goes elsewhere
+            let (replacement, is_cancel) =
+                if cancel_hashes.is_empty() && replacements < TX_MAX_REPLACEMENTS {
+                    replacements += 1;
+                    (tx.clone(), false)
+                } else {
+                    tracing::warn!("Cancelling stuck transaction {} on {:?}", id, chain);
+                    let cancel = TransactionRequest::default()
+                        .with_from(self.address)
+                        .with_to(self.address)
+                        .with_value(U256::ZERO)
+                        .with_chain_id(self.network.chainid)
+                        .with_gas_limit(21_000);
+                    (cancel, true)
+                };
+
+            sent_at_block = Some(block);
+            let new_hash = match self.send(replacement, nonce, fees).await {
+                Ok(new_hash) => new_hash,
+                Err(e) => {
+                    tracing::warn!("Could not replace {} on {:?}, still waiting: {}", id, chain, e);
+                    continue;
+                }
+            };
+            if is_cancel {
+                cancel_hashes.push(new_hash);
+            }
+
+            self.emit(TxEvent::Replaced {
+                chain,
+                id: id.to_string(),
+                old_hash: *hashes.last().unwrap(),
+                hash: new_hash,
+            });
+            hashes.push(new_hash);
+        }
+    }
+
+    /// Amount of the ERC20 `token` the signer received in the mined transaction `hash`, summed
+    /// over its `Transfer` logs.
+    pub async fn received(&self, hash: TxHash, token: Address) -> Result<U256, anyhow::Error> {
+        let receipt = self
+            .provider
+            .get_transaction_receipt(hash)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No receipt for {}", hash))?;
+
+        let received = receipt
+            .inner
+            .logs()
+            .iter()
+            .filter(|log| log.address() == token)
+            .filter_map(|log| log.log_decode::<IERC20::Transfer>().ok())
+            .filter(|transfer| transfer.inner.data.to == self.address)
+            .fold(U256::ZERO, |total, transfer| total + transfer.inner.data.value);
+
+        Ok(received)
+    }
+
+    /// Receipt of whichever of `hashes` was mined. They all share one nonce, so at most one is.
+    async fn find_receipt(
+        &self,
+        hashes: &[TxHash],
+    ) -> Result<Option<TransactionReceipt>, anyhow::Error> {
+        for hash in hashes {
+            if let Some(receipt) = self.provider.get_transaction_receipt(*hash).await? {
+                return Ok(Some(receipt));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Final event for `nonce` once one of `hashes` was mined or the nonce went to a transaction
+    /// we did not send, `None` while it is still pending.
+    async fn nonce_outcome(
+        &self,
+        id: &str,
+        nonce: u64,
+        hashes: &[TxHash],
+        cancel_hashes: &[TxHash],
+    ) -> Result<Option<TxEvent>, anyhow::Error> {
+        let dropped = TxEvent::Dropped {
+            chain: self.network.chain,
+            id: id.to_string(),
+            nonce,
+        };
+
+        if let Some(receipt) = self.find_receipt(hashes).await? {
+            let cancelled = cancel_hashes.contains(&receipt.transaction_hash);
+            return Ok(Some(if cancelled { dropped } else { self.receipt_event(id, receipt) }));
+        }
+
+        let mined_nonce = self.provider.get_transaction_count(self.address).latest().await?;
+        if mined_nonce <= nonce {
+            return Ok(None);
+        }
+
+        // the nonce is used, one of ours may have been mined since the receipts were polled
+        match self.find_receipt(hashes).await? {
+            Some(receipt) if !cancel_hashes.contains(&receipt.transaction_hash) => {
+                Ok(Some(self.receipt_event(id, receipt)))
+            }
+            Some(_) => Ok(Some(dropped)),
+            None => {
+                self.reset_nonce().await;
+                Ok(Some(dropped))
+            }
+        }
+    }
+
+    fn receipt_event(&self, id: &str, receipt: TransactionReceipt) -> TxEvent {
+        let chain = self.network.chain;
+        let hash = receipt.transaction_hash;
+        let block = receipt.block_number.unwrap_or_default();
+        let gas_used = receipt.gas_used;
+
+        if receipt.status() {
+            TxEvent::Included {
+                chain,
+                id: id.to_string(),
+                hash,
+                block,
+                gas_used,
+            }
+        } else {
+            TxEvent::Reverted {
+                chain,
+                id: id.to_string(),
+                hash,
+                block,
+                gas_used,
+            }
+        }
+    }
+}
@@ -28,21 +28,26 @@ pub fn create_tycho_client(
     }
 }
 
+#[tracing::instrument(skip_all, fields(chain = %network.name))]
 pub async fn get_tokens(
     network: &Network,
     api_key: String,
     config_tokens: Vec<TokenConfig>,
 ) -> Result<HashMap<Bytes, Token>, anyhow::Error> {
-    let client = create_tycho_client(network, api_key).unwrap();
+    let client = create_tycho_client(network, api_key)?;
 
     tracing::info!("Getting tokens for network {}", network.name);
 
-    let chain = Chain::from_str(&network.name).unwrap();
+    let chain = Chain::from_str(&network.name)
+        .map_err(|e| anyhow::anyhow!("Unknown chain {}: {:?}", network.name, e))?;
 
-    let token_addresses: Vec<Bytes> = config_tokens
+    let token_addresses = config_tokens
         .iter()
-        .map(|s| Bytes::from_str(s.address.as_str()).unwrap())
-        .collect();
+        .map(|s| {
+            Bytes::from_str(s.address.as_str())
+                .map_err(|e| anyhow::anyhow!("Invalid address for {}: {:?}", s.symbol, e))
+        })
+        .collect::<Result<Vec<Bytes>, _>>()?;
 
     let request = TokensRequestBody {
         token_addresses: Some(token_addresses),
@@ -55,11 +60,14 @@ pub async fn get_tokens(
         },
     };
 
-    let tokens_response = client.get_tokens(&request).await;
+    let tokens_response = client
+        .get_tokens(&request)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch tokens: {:?}", e))?;
 
     let mut tokens: HashMap<Bytes, Token> = HashMap::new();
 
-    for token in tokens_response.unwrap().tokens {
+    for token in tokens_response.tokens {
         tokens.insert(
             token.address.clone(),
             Token {
@@ -1,28 +1,307 @@
+use crate::feed::Feed;
+use crate::recorder::PriceRecorder;
 use serde::{Deserialize, Serialize};
 use tycho_common::models::Chain;
+use alloy::primitives::TxHash;
+use num_bigint::BigUint;
+use tycho_simulation::protocol::models::ProtocolComponent;
 use tycho_simulation::{models::Token, protocol::state::ProtocolSim};
-use petgraph::graph::DiGraph;
-use std::collections::HashMap;
+use petgraph::stable_graph::StableDiGraph;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Instant, SystemTime};
+use tycho_common::Bytes;
 use petgraph::prelude::{EdgeIndex, NodeIndex};
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TokenConfig {
     pub symbol: String,
     pub address: String,
+    /// Starting inventory in token units, fetched from the RPC when omitted
+    #[serde(default)]
+    pub inventory: Option<f64>,
+    /// Balance the rebalancing planner steers towards, in token units
+    #[serde(default)]
+    pub target_inventory: Option<f64>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChainConfig {
     pub name: String,
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+    /// Protocols to index on this chain, all supported ones when empty
+    #[serde(default)]
+    pub protocols: Vec<String>,
     pub tokens: Vec<TokenConfig>,
+    /// Contract that borrows, swaps through the router and repays in one transaction, enables
+    /// flash-loan execution of single-chain cycles
+    #[serde(default)]
+    pub flash_executor: Option<String>,
+    /// How the router pulls the swap input from the signer
+    #[serde(default)]
+    pub transfer_mode: TransferMode,
+    /// Blocks without an update after which the chain's stream counts as stale, defaults to
+    /// `CHAIN_STALE_AFTER_BLOCKS`
+    #[serde(default)]
+    pub stale_after_blocks: Option<u32>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PegType {
+    /// Canonical bridge representation, redeemable 1:1
+    #[default]
+    Canonical,
+    /// Independently issued or wrapped asset that only trades close to the canonical one
+    Soft,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetTokenConfig {
+    pub chain: String,
+    pub address: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetGroupConfig {
+    pub name: String,
+    #[serde(default)]
+    pub peg: PegType,
+    pub tokens: Vec<AssetTokenConfig>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionMode {
+    /// Fill accepted opportunities against the next block's pool states, no transactions sent
+    #[default]
+    Paper,
+    Live,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferMode {
+    /// Plain ERC-20 approval of the router, which pulls the input with `transferFrom`
+    #[default]
+    Approval,
+    /// Approval of Permit2 plus a signed permit for the router sent along with every swap
+    Permit2,
+}
+
+/// Cost of moving a token between two chains: `fixed_fee` in units of the bridged token plus
+/// `fee_bps` of the amount
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BridgeConfig {
+    pub from: String,
+    pub to: String,
+    #[serde(default)]
+    pub fee_bps: u64,
+    #[serde(default)]
+    pub fixed_fee: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RebalanceConfig {
+    /// Relative drift from the target tolerated before proposing a transfer
+    #[serde(default = "default_rebalance_tolerance")]
+    pub tolerance: f64,
+    #[serde(default = "default_rebalance_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default)]
+    pub bridges: Vec<BridgeConfig>,
+}
+
+fn default_rebalance_tolerance() -> f64 {
+    0.1
+}
+
+fn default_rebalance_interval_secs() -> u64 {
+    300
+}
+
+impl Default for RebalanceConfig {
+    fn default() -> Self {
+        Self {
+            tolerance: default_rebalance_tolerance(),
+            interval_secs: default_rebalance_interval_secs(),
+            bridges: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Config {
+    /// Only required in live mode or when a token's inventory is fetched from the RPC
+    #[serde(default)]
     pub private_key: String,
+    /// Maximum slippage tolerance a leg may need for the volatility of its pools, in basis
+    /// points on top of the fixed `SLIPPAGE_BASE_BPS`
     pub risk_param: u64,
-    pub rpc_url: String,
+    #[serde(default)]
+    pub execution_mode: ExecutionMode,
     pub chains: Vec<ChainConfig>,
+    #[serde(default)]
+    pub assets: Vec<AssetGroupConfig>,
+    #[serde(default)]
+    pub rebalance: RebalanceConfig,
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub journal: JournalConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    /// Price history recording, disabled when unset
+    #[serde(default)]
+    pub recorder: Option<RecorderConfig>,
+    #[serde(default)]
+    pub alerts: AlertConfig,
+    #[serde(default)]
+    pub dashboard: DashboardConfig,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DashboardConfig {
+    /// Input size, in token units per asset symbol, at which the dashboard quotes executable
+    /// prices. Pairs without a size for both assets show no spreads
+    #[serde(default)]
+    pub reference_sizes: HashMap<Symbol, f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertConfig {
+    /// Every alert is POSTed as JSON to each URL, alerting is off when empty
+    #[serde(default)]
+    pub webhooks: Vec<String>,
+    /// Worst-case profit, in token units per asset symbol, above which opportunities alert
+    #[serde(default)]
+    pub min_profit: HashMap<Symbol, f64>,
+    /// Stream errors within `error_window_secs` that raise an ingestion alert
+    #[serde(default = "default_alert_error_threshold")]
+    pub error_threshold: usize,
+    #[serde(default = "default_alert_error_window_secs")]
+    pub error_window_secs: u64,
+    /// Alerts with the same key are dropped for this long after one was sent
+    #[serde(default = "default_alert_dedup_secs")]
+    pub dedup_secs: u64,
+    #[serde(default = "default_alert_max_per_minute")]
+    pub max_per_minute: usize,
+}
+
+fn default_alert_error_threshold() -> usize {
+    5
+}
+
+fn default_alert_error_window_secs() -> u64 {
+    60
+}
+
+fn default_alert_dedup_secs() -> u64 {
+    300
+}
+
+fn default_alert_max_per_minute() -> usize {
+    10
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            webhooks: vec![],
+            min_profit: HashMap::new(),
+            error_threshold: default_alert_error_threshold(),
+            error_window_secs: default_alert_error_window_secs(),
+            dedup_secs: default_alert_dedup_secs(),
+            max_per_minute: default_alert_max_per_minute(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRotation {
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    #[serde(default)]
+    pub format: LogFormat,
+    /// Directory for log files next to stdout, no file output when unset
+    #[serde(default)]
+    pub directory: Option<String>,
+    #[serde(default)]
+    pub rotation: LogRotation,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordFormat {
+    #[default]
+    Csv,
+    Parquet,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecorderConfig {
+    pub directory: String,
+    #[serde(default)]
+    pub format: RecordFormat,
+    /// A new file is started every period, `never` keeps appending to one file
+    #[serde(default)]
+    pub rotation: LogRotation,
+    /// Input sizes, in token units per asset symbol, at which executable prices are quoted
+    #[serde(default)]
+    pub reference_sizes: HashMap<Symbol, Vec<f64>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerConfig {
+    /// Address the HTTP server (metrics, API) listens on
+    #[serde(default = "default_server_listen")]
+    pub listen: String,
+}
+
+fn default_server_listen() -> String {
+    "0.0.0.0:9100".to_string()
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen: default_server_listen(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalConfig {
+    /// SQLite database of opportunities, decisions and transactions
+    #[serde(default = "default_journal_path")]
+    pub path: String,
+}
+
+fn default_journal_path() -> String {
+    "journal.db".to_string()
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            path: default_journal_path(),
+        }
+    }
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
@@ -35,7 +314,18 @@ pub struct Network {
     pub tycho_url: String,
     pub router: String,
     pub permit2: String,
+    pub balancer_vault: Option<String>,
+    pub pool_manager: String,
     pub block_time_ms: u64,
+    /// Set from the chain's config, like an `rpc` override
+    #[serde(default)]
+    pub transfer_mode: TransferMode,
+    /// Set from the chain's config, like an `rpc` override
+    #[serde(default)]
+    pub flash_executor: Option<String>,
+    /// Set from the chain's config, like an `rpc` override
+    #[serde(default)]
+    pub stale_after_blocks: Option<u32>,
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
@@ -67,6 +357,10 @@ impl Protocol {
         }
     }
 
+    pub fn is_vm(&self) -> bool {
+        matches!(self, Protocol::VmBalancerV2 | Protocol::VmCurve)
+    }
+
     pub fn to_str(&self) -> &'static str {
         match self {
             Protocol::UniswapV2 => "uniswap_v2",
@@ -82,10 +376,18 @@ impl Protocol {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct AssetGroup {
+    pub name: Symbol,
+    pub peg: PegType,
+    pub members: HashSet<(Chain, Bytes)>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct TokenNode {
   pub symbol: String,
-  pub tokens: HashMap<Chain, Token>,
+  pub peg: PegType,
+  pub tokens: HashMap<Chain, Vec<Token>>,
 }
 
 #[derive(Debug, Clone)]
@@ -93,18 +395,470 @@ pub struct PriceEdge {
     pub chain: Chain,
     pub protocol: Protocol,
     pub pool_address: String,
+    pub component: ProtocolComponent,
     pub state: Box<dyn ProtocolSim>,
     pub to_token: Token,
     pub from_token: Token,
     pub price: f64,
 }
 
+/// Serializable view of a `PriceEdge`, without the simulation state
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceEdgeView {
+    pub chain: Chain,
+    pub protocol: String,
+    pub pool_address: PoolAddress,
+    pub from_token: Token,
+    pub to_token: Token,
+    pub price: f64,
+}
+
+impl From<&PriceEdge> for PriceEdgeView {
+    fn from(edge: &PriceEdge) -> Self {
+        Self {
+            chain: edge.chain,
+            protocol: edge.protocol.to_str().to_string(),
+            pool_address: edge.pool_address.clone(),
+            from_token: edge.from_token.clone(),
+            to_token: edge.to_token.clone(),
+            price: edge.price,
+        }
+    }
+}
+
+/// Best and worst price of one asset pair across every chain and pool
+#[derive(Debug, Clone, Serialize)]
+pub struct SpreadView {
+    pub quote: Symbol,
+    pub best: PriceEdgeView,
+    pub worst: PriceEdgeView,
+    pub spread_bps: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OpportunityLegView {
+    pub chain: Chain,
+    /// Token symbols along the path, starting with the input token
+    pub path: Vec<Symbol>,
+    pub pools: Vec<PoolAddress>,
+    pub amount_in: String,
+    pub amount_out: String,
+    pub min_amount_out: String,
+    pub tolerance_bps: u64,
+}
+
+/// Serializable view of an `Opportunity`, amounts are raw token amounts as decimal strings
+#[derive(Debug, Clone, Serialize)]
+pub struct OpportunityView {
+    pub id: String,
+    pub legs: Vec<OpportunityLegView>,
+}
+
+impl From<&Opportunity> for OpportunityView {
+    fn from(opportunity: &Opportunity) -> Self {
+        let legs = opportunity
+            .legs
+            .iter()
+            .map(|leg| OpportunityLegView {
+                chain: leg.chain,
+                path: leg
+                    .hops
+                    .first()
+                    .map(|hop| hop.from_token.symbol.clone())
+                    .into_iter()
+                    .chain(leg.hops.iter().map(|hop| hop.to_token.symbol.clone()))
+                    .collect(),
+                pools: leg.hops.iter().map(|hop| hop.pool_address.clone()).collect(),
+                amount_in: leg.amount_in().to_string(),
+                amount_out: leg.amount_out().to_string(),
+                min_amount_out: leg.min_amount_out.to_string(),
+                tolerance_bps: leg.tolerance_bps,
+            })
+            .collect();
+
+        Self {
+            id: opportunity.id.clone(),
+            legs,
+        }
+    }
+}
+
+/// Event pushed to feed subscribers
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum FeedEvent {
+    Opportunity(OpportunityView),
+    PriceChange(PriceEdgeView),
+}
+
+impl FeedEvent {
+    pub fn chains(&self) -> Vec<Chain> {
+        match self {
+            FeedEvent::Opportunity(opportunity) => {
+                opportunity.legs.iter().map(|leg| leg.chain).collect()
+            }
+            FeedEvent::PriceChange(edge) => vec![edge.chain],
+        }
+    }
+
+    pub fn symbols(&self) -> Vec<&str> {
+        match self {
+            FeedEvent::Opportunity(opportunity) => opportunity
+                .legs
+                .iter()
+                .flat_map(|leg| leg.path.iter().map(String::as_str))
+                .collect(),
+            FeedEvent::PriceChange(edge) => {
+                vec![edge.from_token.symbol.as_str(), edge.to_token.symbol.as_str()]
+            }
+        }
+    }
+}
+
+/// Condition reported to the alert webhooks
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertEvent {
+    Opportunity {
+        opportunity: OpportunityView,
+        /// Worst-case profit per asset, in token units
+        profit: HashMap<Symbol, f64>,
+    },
+    StreamDisconnected {
+        chain: Chain,
+    },
+    IngestionErrors {
+        chain: Chain,
+        errors: usize,
+        window_secs: u64,
+    },
+    RiskLimit {
+        limit: String,
+        /// What hit the limit, e.g. a chain or token, part of the dedup key
+        subject: String,
+        message: String,
+    },
+}
+
+impl AlertEvent {
+    /// Alerts with the same key are deduplicated, an opportunity is keyed by its route so a
+    /// persisting spread alerts once.
+    pub fn key(&self) -> String {
+        match self {
+            AlertEvent::Opportunity { opportunity, .. } => {
+                let route = opportunity
+                    .legs
+                    .iter()
+                    .map(|leg| format!("{:?}:{}", leg.chain, leg.path.join(">")))
+                    .collect::<Vec<_>>()
+                    .join("|");
+                format!("opportunity:{route}")
+            }
+            AlertEvent::StreamDisconnected { chain } => format!("stream_disconnected:{chain:?}"),
+            AlertEvent::IngestionErrors { chain, .. } => format!("ingestion_errors:{chain:?}"),
+            AlertEvent::RiskLimit { limit, subject, .. } => format!("risk_limit:{limit}:{subject}"),
+        }
+    }
+
+    /// One line description for chat.
+    pub fn summary(&self) -> String {
+        match self {
+            AlertEvent::Opportunity { opportunity, profit } => {
+                let mut profit = profit
+                    .iter()
+                    .filter(|(_, amount)| **amount > 0.0)
+                    .map(|(symbol, amount)| format!("{amount:.6} {symbol}"))
+                    .collect::<Vec<_>>();
+                profit.sort();
+                format!(
+                    "Opportunity {} with worst-case profit {}",
+                    opportunity.id,
+                    profit.join(", ")
+                )
+            }
+            AlertEvent::StreamDisconnected { chain } => {
+                format!("Stream for {chain:?} disconnected")
+            }
+            AlertEvent::IngestionErrors {
+                chain,
+                errors,
+                window_secs,
+            } => format!("{errors} stream errors on {chain:?} within {window_secs}s"),
+            AlertEvent::RiskLimit { limit, message, .. } => {
+                format!("Risk limit {limit} breached: {message}")
+            }
+        }
+    }
+}
+
 pub type Symbol = String;
 pub type PoolAddress = String;
 
 #[derive(Debug, Clone)]
 pub struct ArbitrageGraph {
-    pub graph: DiGraph<TokenNode, PriceEdge>,
+    pub graph: StableDiGraph<TokenNode, PriceEdge>,
+    pub edges_map: HashMap<PoolAddress, Vec<EdgeIndex>>,
+    pub nodes_map: HashMap<Symbol, NodeIndex>,
+    pub token_nodes: HashMap<(Chain, Bytes), NodeIndex>,
+    pub asset_groups: Vec<AssetGroup>,
+    /// Recent spot prices of each edge, oldest first
+    pub price_history: HashMap<EdgeIndex, VecDeque<(Instant, f64)>>,
+    /// Receives every price change when set
+    pub feed: Option<Feed>,
+    pub chain_status: HashMap<Chain, ChainStatus>,
+    /// Receives every edge update when set
+    pub recorder: Option<PriceRecorder>,
+    /// Flash-loan sources by borrowed token, the zero-capital entry edges of single-chain
+    /// cycles. Only chains with a flash executor have any.
+    pub flash_entries: HashMap<(Chain, Bytes), Vec<FlashLoanSource>>,
+    /// Set once the tokens of every chain are added
+    pub initialized: bool,
+}
+
+/// Edge of a `GraphSnapshot`, without the simulation state
+#[derive(Debug, Clone)]
+pub struct EdgeSnapshot {
+    pub view: PriceEdgeView,
+    /// Assets of both sides, as merged in the graph
+    pub from_asset: Symbol,
+    pub to_asset: Symbol,
+    /// Output per unit of input when swapping the reference size of the input asset, None
+    /// without a size or when the pool cannot fill it
+    pub executable_price: Option<f64>,
+}
+
+/// Read-only copy of the graph published to the HTTP API and the dashboard: its nodes, edge
+/// views and chain status, keeping the node and edge indices of the live graph
+#[derive(Debug, Clone, Default)]
+pub struct GraphSnapshot {
+    pub graph: StableDiGraph<TokenNode, EdgeSnapshot>,
     pub edges_map: HashMap<PoolAddress, Vec<EdgeIndex>>,
     pub nodes_map: HashMap<Symbol, NodeIndex>,
+    pub chain_status: HashMap<Chain, ChainStatus>,
+    pub initialized: bool,
+}
+
+/// When a block update was pulled from a chain's stream, carried along to everything derived
+/// from it to measure latency per stage
+#[derive(Debug, Clone, Copy)]
+pub struct BlockTiming {
+    pub chain: Chain,
+    pub block: u64,
+    pub received_at: Instant,
+    /// Wall clock at receipt, compared against the block timestamp
+    pub received_at_system: SystemTime,
+}
+
+/// Last block update applied to the graph for a chain
+#[derive(Debug, Clone, Copy)]
+pub struct ChainStatus {
+    pub timing: BlockTiming,
+    pub updated_at: Instant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainHealthView {
+    pub chain: String,
+    /// Latest block applied, None until the first snapshot arrived
+    pub block: Option<u64>,
+    pub last_update_secs: Option<f64>,
+    pub stale: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthView {
+    pub initialized: bool,
+    pub chains: Vec<ChainHealthView>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    /// YAML path of the offending value, e.g. `chains[1].tokens[3].address`
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ConfigDiff {
+    pub risk_param: Option<u64>,
+    pub reloaded_chains: Vec<ChainConfig>,
+}
+
+/// A single swap through one pool, with amounts from the sizing step
+#[derive(Debug, Clone)]
+pub struct SwapHop {
+    pub pool_address: PoolAddress,
+    pub protocol: Protocol,
+    pub component: ProtocolComponent,
+    pub from_token: Token,
+    pub to_token: Token,
+    pub amount_in: BigUint,
+    pub amount_out: BigUint,
+}
+
+/// The consecutive hops of an opportunity that execute on one chain
+#[derive(Debug, Clone)]
+pub struct OpportunityLeg {
+    pub chain: Chain,
+    pub hops: Vec<SwapHop>,
+    /// Slippage tolerance applied to the leg's output, in basis points
+    pub tolerance_bps: u64,
+    /// Output below which the leg reverts, derived from `tolerance_bps`
+    pub min_amount_out: BigUint,
+}
+
+impl OpportunityLeg {
+    pub fn amount_in(&self) -> BigUint {
+        self.hops.first().map(|h| h.amount_in.clone()).unwrap_or_default()
+    }
+
+    pub fn amount_out(&self) -> BigUint {
+        self.hops.last().map(|h| h.amount_out.clone()).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Opportunity {
+    pub id: String,
+    pub legs: Vec<OpportunityLeg>,
+    /// Block update the opportunity was detected on
+    pub timing: BlockTiming,
+    /// Set when the single leg is funded by a flash loan instead of inventory
+    pub flash: Option<FlashLoanSource>,
+}
+
+/// What happened to a detected opportunity, as recorded in the journal
+#[derive(Debug, Clone)]
+pub enum Decision {
+    Rejected { reason: String },
+    PaperFilled,
+    Executed,
+}
+
+impl Decision {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Decision::Rejected { .. } => "rejected",
+            Decision::PaperFilled => "paper_filled",
+            Decision::Executed => "executed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FlashLoanProvider {
+    BalancerV2 = 0,
+    UniswapV3 = 1,
+    UniswapV4 = 2,
+}
+
+/// A place to borrow `token` without capital, i.e. the zero-capital entry edge of a cycle
+#[derive(Debug, Clone)]
+pub struct FlashLoanSource {
+    pub provider: FlashLoanProvider,
+    pub chain: Chain,
+    /// Vault, pool or pool manager the loan is taken from
+    pub lender: Bytes,
+    pub token: Token,
+    /// Fee on the borrowed amount, in hundredths of a basis point
+    pub fee_pips: u32,
+}
+
+/// Borrow `amount` from `source`, run `leg` and repay `amount + fee` in one transaction
+#[derive(Debug, Clone)]
+pub struct FlashExecutionPlan {
+    pub opportunity_id: String,
+    pub source: FlashLoanSource,
+    pub leg: OpportunityLeg,
+    pub amount: BigUint,
+    pub fee: BigUint,
+    /// Profit left when the leg only returns its minimum output, after repaying the loan
+    pub min_profit: BigUint,
+}
+
+/// Calldata for one leg, ready to be signed and sent to the router
+#[derive(Debug, Clone)]
+pub struct EncodedLeg {
+    pub chain: Chain,
+    pub to: Bytes,
+    pub value: BigUint,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationResult {
+    pub chain: Chain,
+    pub expected_amount_out: BigUint,
+    pub simulated_amount_out: BigUint,
+    pub min_amount_out: BigUint,
+    pub gas_used: u64,
+    pub accepted: bool,
+}
+
+/// Outcome of executing (or paper filling) one leg
+#[derive(Debug, Clone)]
+pub struct LegFill {
+    pub opportunity_id: String,
+    /// Position of the leg in `Opportunity::legs`
+    pub leg_index: usize,
+    pub leg: OpportunityLeg,
+    /// Whether the leg's swap went through
+    pub filled: bool,
+    /// Run on a flash loan by the chain's executor contract, which keeps the output
+    pub flash: bool,
+    /// Realized output, `None` when the leg was not filled or the output is unknown
+    pub amount_out: Option<BigUint>,
+    /// Gas estimated by the pre-submission simulation, `None` for paper fills
+    pub simulated_gas: Option<u64>,
+}
+
+/// Lifecycle of a transaction sent by a chain's `TxManager`
+#[derive(Debug, Clone)]
+pub enum TxEvent {
+    Submitted { chain: Chain, id: String, hash: TxHash, nonce: u64 },
+    Replaced { chain: Chain, id: String, old_hash: TxHash, hash: TxHash },
+    Included { chain: Chain, id: String, hash: TxHash, block: u64, gas_used: u64 },
+    Reverted { chain: Chain, id: String, hash: TxHash, block: u64, gas_used: u64 },
+    /// Never included, either cancelled or its nonce was consumed by another transaction
+    Dropped { chain: Chain, id: String, nonce: u64 },
+}
+
+#[derive(Debug, Clone)]
+pub struct BridgeTransfer {
+    pub asset: Symbol,
+    pub from_chain: Chain,
+    pub from_token: Token,
+    pub to_chain: Chain,
+    pub to_token: Token,
+    /// In units of `from_token`
+    pub amount: f64,
+    pub estimated_cost: f64,
+}
+
+/// Inventory drift that no bridge covers cheaply; trades moving `asset` out of `sell_on` and
+/// into `buy_on` should be favoured
+#[derive(Debug, Clone)]
+pub struct TradePreference {
+    pub asset: Symbol,
+    pub sell_on: Chain,
+    pub buy_on: Chain,
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RebalancePlan {
+    pub transfers: Vec<BridgeTransfer>,
+    pub preferences: Vec<TradePreference>,
+}
+
+/// Permit2 `PermitSingle` signed for the router, valid until `sig_deadline`
+#[derive(Debug, Clone)]
+pub struct SignedPermit {
+    pub token: Bytes,
+    pub spender: Bytes,
+    pub nonce: u64,
+    pub expiration: u64,
+    pub sig_deadline: u64,
+    pub signature: Vec<u8>,
 }
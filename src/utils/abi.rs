@@ -0,0 +1,55 @@
+use alloy::sol;
+
+sol! {
+    #[sol(rpc)]
+    interface IERC20 {
+        function balanceOf(address owner) external view returns (uint256);
+        function allowance(address owner, address spender) external view returns (uint256);
+        function approve(address spender, uint256 amount) external returns (bool);
+
+        event Transfer(address indexed from, address indexed to, uint256 value);
+    }
+
+    #[sol(rpc)]
+    interface IPermit2 {
+        function allowance(address user, address token, address spender)
+            external
+            view
+            returns (uint160 amount, uint48 expiration, uint48 nonce);
+    }
+
+    /// Contract configured as a chain's `flash_executor`, deployed by the operator. Borrows
+    /// `amount` of `token` from `lender`, calls `router` with `swapData`, repays the loan and
+    /// reverts unless at least `minProfit` of `token` is left. An implementation must:
+    /// - only accept `execute` from the signer;
+    /// - borrow through `provider` (0: Balancer V2 vault `flashLoan`, 1: Uniswap V3 pool
+    ///   `flash`, 2: Uniswap V4 pool manager `unlock`) and run the swap from the provider's
+    ///   callback, rejecting callbacks from anything but `lender`;
+    /// - approve `router` for `amount` before the call, `swapData` pulls the input from and
+    ///   pays the output to the executor with `transferFrom`;
+    /// - repay `amount` plus the provider's fee within the callback and keep the profit.
+    interface IFlashArbExecutor {
+        function execute(
+            uint8 provider,
+            address lender,
+            address token,
+            uint256 amount,
+            address router,
+            bytes calldata swapData,
+            uint256 minProfit
+        ) external;
+    }
+
+    struct PermitDetails {
+        address token;
+        uint160 amount;
+        uint48 expiration;
+        uint48 nonce;
+    }
+
+    struct PermitSingle {
+        PermitDetails details;
+        address spender;
+        uint256 sigDeadline;
+    }
+}
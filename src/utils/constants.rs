@@ -1,9 +1,41 @@
-use crate::types::Network;
+use crate::types::{Network, TransferMode};
 use tycho_common::models::Chain;
 
 pub const TYCHO_API_KEY: &str = "sampletoken";
 pub const TVL_LOWER_BOUND: f64 = 20.;
 pub const TVL_UPPER_BOUND: f64 = 40.0;
+pub const CONFIG_PATH: &str = "config.yml";
+pub const CONFIG_POLL_INTERVAL_MS: u64 = 2000;
+pub const FEED_CAPACITY: usize = 4096;
+pub const CHAIN_STALE_AFTER_BLOCKS: u32 = 10;
+pub const CHAIN_STALE_MIN_SECS: u64 = 30;
+pub const API_SNAPSHOT_INTERVAL_MS: u64 = 500;
+pub const BLOCK_LAG_ANCHOR_SECS: u64 = 60;
+pub const DASHBOARD_REFRESH_MS: u64 = 250;
+pub const DASHBOARD_MAX_OPPORTUNITIES: usize = 10;
+pub const LOG_FILE_NAME: &str = "multiarb.log";
+pub const ALERT_CAPACITY: usize = 256;
+pub const ALERT_TIMEOUT_SECS: u64 = 10;
+pub const RECORDER_CAPACITY: usize = 65_536;
+pub const RECORDER_PARQUET_ROWS: usize = 100_000;
+pub const TX_MIN_POLL_INTERVAL_MS: u64 = 250;
+pub const TX_STUCK_AFTER_BLOCKS: u64 = 5;
+pub const TX_MAX_REPLACEMENTS: u32 = 3;
+pub const TX_FEE_BUMP_PERCENT: u128 = 15;
+pub const TX_GAS_LIMIT_BUFFER_PERCENT: u64 = 20;
+pub const PERMIT_VALIDITY_SECS: u64 = 30 * 24 * 60 * 60;
+pub const PERMIT_REFRESH_MARGIN_SECS: u64 = 24 * 60 * 60;
+pub const APPROVAL_CHECK_INTERVAL_SECS: u64 = 60 * 60;
+pub const PRICE_HISTORY_LEN: usize = 64;
+pub const SLIPPAGE_BASE_BPS: u64 = 5;
+pub const SLIPPAGE_MAX_BPS: u64 = 200;
+pub const SLIPPAGE_CONFIDENCE: f64 = 3.0;
+pub const SOFT_PEG_HAIRCUT_BPS: u64 = 50;
+pub const DETECTION_MAX_HOPS: usize = 3;
+pub const DETECTION_MIN_RETURN: f64 = 1.001;
+pub const DETECTION_MAX_CYCLES: usize = 8;
+pub const SIZING_STEPS: usize = 8;
+pub const FLASH_SIZING_STEPS: usize = 24;
 
 pub fn network(name: String) -> Option<Network> {
     networks().into_iter().find(|n| n.name == name)
@@ -20,7 +52,12 @@ pub fn networks() -> Vec<Network> {
             tycho_url: "tycho-beta.propellerheads.xyz".to_string(),
             permit2: "0x000000000022D473030F116dDEE9F6B43aC78BA3".to_string(),
             router: "0xfD0b31d2E955fA55e3fa641Fe90e08b677188d35".to_string(),
+            balancer_vault: Some("0xBA12222222228d8Ba445958a75a0704d566BF2C8".to_string()),
+            pool_manager: "0x000000000004444c5dc75cB358380D2e3dE08A90".to_string(),
             block_time_ms: 12000,
+            transfer_mode: TransferMode::default(),
+            flash_executor: None,
+            stale_after_blocks: None,
         },
         Network {
             chainid: 8453,
@@ -31,7 +68,12 @@ pub fn networks() -> Vec<Network> {
             tycho_url: "tycho-base-beta.propellerheads.xyz".to_string(),
             permit2: "0x000000000022D473030F116dDEE9F6B43aC78BA3".to_string(),
             router: "0xea3207778e39EB02D72C9D3c4Eac7E224ac5d369".to_string(),
+            balancer_vault: Some("0xBA12222222228d8Ba445958a75a0704d566BF2C8".to_string()),
+            pool_manager: "0x498581fF718922c3f8e6A244956aF099B2652b2b".to_string(),
             block_time_ms: 250,
+            transfer_mode: TransferMode::default(),
+            flash_executor: None,
+            stale_after_blocks: None,
         },
         Network {
             chainid: 130,
@@ -42,7 +84,12 @@ pub fn networks() -> Vec<Network> {
             tycho_url: "tycho-unichain-beta.propellerheads.xyz".to_string(),
             permit2: "0x000000000022D473030F116dDEE9F6B43aC78BA3".to_string(),
             router: "0xFfA5ec2e444e4285108e4a17b82dA495c178427B".to_string(),
+            balancer_vault: None,
+            pool_manager: "0x1F98400000000000000000000000000000000004".to_string(),
             block_time_ms: 1000,
+            transfer_mode: TransferMode::default(),
+            flash_executor: None,
+            stale_after_blocks: None,
         },
     ]
 }
@@ -1,2 +1,5 @@
+pub mod abi;
 pub mod constants;
-pub mod graph;
\ No newline at end of file
+pub mod graph;
+pub mod rpc;
+pub mod units;
@@ -0,0 +1,17 @@
+use crate::types::Network;
+use alloy::providers::{DynProvider, Provider, ProviderBuilder};
+use alloy::signers::local::PrivateKeySigner;
+use std::str::FromStr;
+
+pub fn create_provider(network: &Network) -> Result<DynProvider, anyhow::Error> {
+    let provider = ProviderBuilder::new()
+        .connect_http(network.rpc.parse()?)
+        .erased();
+
+    Ok(provider)
+}
+
+pub fn create_signer(private_key: &str) -> Result<PrivateKeySigner, anyhow::Error> {
+    PrivateKeySigner::from_str(private_key)
+        .map_err(|e| anyhow::anyhow!("Invalid private key: {:?}", e))
+}
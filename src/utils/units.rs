@@ -0,0 +1,37 @@
+use alloy::primitives::U256;
+use num_bigint::{BigInt, BigUint, Sign};
+
+/// Converts a raw token amount to a float in token units.
+pub fn to_units(amount: &BigUint, decimals: usize) -> f64 {
+    amount.to_string().parse::<f64>().unwrap_or_default() / 10f64.powi(decimals as i32)
+}
+
+/// Converts a signed raw token amount, such as a balance change, to a float in token units.
+pub fn to_signed_units(amount: &BigInt, decimals: usize) -> f64 {
+    let units = to_units(amount.magnitude(), decimals);
+    if amount.sign() == Sign::Minus { -units } else { units }
+}
+
+/// Converts token units to a raw token amount, truncating below the token's precision.
+pub fn from_units(amount: f64, decimals: usize) -> BigUint {
+    BigUint::from((amount.max(0.0) * 10f64.powi(decimals as i32)) as u128)
+}
+
+/// `amount` reduced by `bps` basis points, rounded down.
+pub fn sub_bps(amount: &BigUint, bps: u64) -> BigUint {
+    amount * BigUint::from(10_000u64.saturating_sub(bps)) / BigUint::from(10_000u64)
+}
+
+/// Converts a raw amount between two token precisions, truncating when precision is lost.
+pub fn rescale(amount: &BigUint, from_decimals: usize, to_decimals: usize) -> BigUint {
+    if to_decimals >= from_decimals {
+        amount * BigUint::from(10u64).pow((to_decimals - from_decimals) as u32)
+    } else {
+        amount / BigUint::from(10u64).pow((from_decimals - to_decimals) as u32)
+    }
+}
+
+/// Raw token amount as a contract call argument.
+pub fn to_u256(amount: &BigUint) -> U256 {
+    U256::from_be_slice(&amount.to_bytes_be())
+}